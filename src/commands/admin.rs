@@ -0,0 +1,154 @@
+use super::record_id;
+use crate::database::{BannerKind, Change};
+use crate::shopify::OrderNumber;
+use crate::{Context, CustomError, Error, staff};
+use poise::CreateReply;
+use poise::serenity_prelude::{CreateAttachment, User};
+
+/// Inspect and repair the summons on an order. Staff only.
+#[poise::command(
+    slash_command,
+    ephemeral,
+    guild_only,
+    subcommands("inspect", "reset", "void"),
+    subcommand_required
+)]
+pub async fn admin(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show everything about an order's summons.
+#[poise::command(slash_command, ephemeral, guild_only)]
+async fn inspect(
+    ctx: Context<'_>,
+    #[description = "Order Number (leave empty for the user's bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+    #[description = "The user whose bonus summons to inspect"] user: Option<User>,
+) -> Result<(), Error> {
+    staff::check(ctx).await?;
+    let id = record_id(order_number, user.as_ref())?;
+    let row = ctx
+        .data()
+        .sheets
+        .get_record(id)
+        .await?
+        .ok_or_else(|| CustomError(format!("There is no record for {id}.")))?;
+
+    let mut message = format!(
+        "**{}** belongs to <@{}> ({}).\n{}",
+        id,
+        row.discord_user_id,
+        row.discord_username,
+        row.pulls.inspection()?
+    );
+    if message.chars().count() > 2000 {
+        message = message.chars().take(1999).collect::<String>() + "…";
+    }
+    let json = serde_json::to_string_pretty(&row.pulls.to_redacted_json())?;
+    ctx.send(
+        CreateReply::default()
+            .content(message)
+            .attachment(CreateAttachment::bytes(json, format!("{id}.json"))),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Clear a stuck active banner, releasing any stock it holds.
+#[poise::command(slash_command, ephemeral, guild_only)]
+async fn reset(
+    ctx: Context<'_>,
+    #[description = "Why the banner is being reset"] reason: String,
+    #[description = "Order Number (leave empty for the user's bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+    #[description = "The user whose bonus summons to reset"] user: Option<User>,
+) -> Result<(), Error> {
+    staff::check(ctx).await?;
+    let data = ctx.data();
+    let id = record_id(order_number, user.as_ref())?;
+
+    let _guard = data.lock.lock().await;
+    let mut row = data
+        .sheets
+        .get_record(id)
+        .await?
+        .ok_or_else(|| CustomError(format!("There is no record for {id}.")))?;
+    row.pulls.reset()?;
+    row.pulls.record(
+        ctx.author().name.to_owned(),
+        Change::Reset {
+            reason: reason.clone(),
+        },
+    );
+    log::info!(
+        "{} reset the active banner of {}: {}",
+        ctx.author().name,
+        id,
+        reason
+    );
+    data.sheets.save(row).await?;
+    ctx.say(format!("Reset the active banner of {id}.")).await?;
+    Ok(())
+}
+
+/// Undo a pull, returning the product to stock.
+#[poise::command(slash_command, ephemeral, guild_only)]
+async fn void(
+    ctx: Context<'_>,
+    #[description = "The kind of banner"] kind: BannerKind,
+    #[description = "The slot that was summoned"]
+    #[min = 1]
+    #[max = 5]
+    slot: usize,
+    #[description = "Why the pull is being voided"] reason: String,
+    #[description = "Order Number (leave empty for the user's bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+    #[description = "The user whose bonus summons to void a pull from"] user: Option<User>,
+    #[description = "The banner's number, as shown by inspect (leave empty for the active banner)"]
+    #[min = 1]
+    index: Option<usize>,
+) -> Result<(), Error> {
+    staff::check(ctx).await?;
+    let data = ctx.data();
+    let id = record_id(order_number, user.as_ref())?;
+
+    let _guard = data.lock.lock().await;
+    let mut row = data
+        .sheets
+        .get_record(id)
+        .await?
+        .ok_or_else(|| CustomError(format!("There is no record for {id}.")))?;
+    let product = row
+        .pulls
+        .void(kind, index.map(|index| index - 1), slot - 1)?;
+    row.pulls.record(
+        ctx.author().name.to_owned(),
+        Change::Void {
+            sku: product.sku.clone(),
+            reason: reason.clone(),
+        },
+    );
+    log::info!(
+        "{} voided a pull of {} from {}: {}",
+        ctx.author().name,
+        product.sku,
+        id,
+        reason
+    );
+    if id.tracks_inventory()
+        && let Err(error) = data
+            .inventory
+            .log_void(row.discord_user_id.clone(), id, product.sku.clone())
+            .await
+    {
+        log::error!("Error returning voided pull to inventory: {}", error);
+    }
+    data.sheets.save(row).await?;
+    data.forget_stats();
+    ctx.say(format!("Voided the pull of {} from {id}.", product.name))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use super::field_value;
+use crate::config::{Pool, Product};
+use crate::{Context, Error};
+use poise::CreateReply;
+use poise::serenity_prelude::CreateEmbed;
+use std::collections::HashMap;
+
+/// See everything you have summoned, across all of your orders.
+#[poise::command(slash_command, ephemeral)]
+pub async fn collection(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let database = data.sheets.database().await?;
+
+    let rows: Vec<_> = database
+        .values()
+        .filter(|row| row.discord_user_id == user_id)
+        .collect();
+    // Daily summons are drawn from their own products, so they are shown separately from the
+    // main catalogue.
+    let counts = count(
+        rows.iter()
+            .filter(|row| row.id.tracks_inventory())
+            .flat_map(|row| row.pulls.owned_products()),
+    );
+    let daily = count(
+        rows.iter()
+            .filter(|row| !row.id.tracks_inventory())
+            .flat_map(|row| row.pulls.owned_products()),
+    );
+    if counts.is_empty() && daily.is_empty() {
+        ctx.say("You have not summoned anything yet.").await?;
+        return Ok(());
+    }
+
+    let catalogue = data.products.iter().count();
+    let collected = data
+        .products
+        .iter()
+        .filter(|product| counts.contains_key(product.sku.as_str()))
+        .count();
+    let mut embed = CreateEmbed::new()
+        .title(format!("{}'s collection", ctx.author().name))
+        .description(format!(
+            "You have collected **{collected} of {catalogue}** products ({}%), and own {} in total.",
+            collected * 100 / catalogue.max(1),
+            counts.values().map(|(_, count)| count).sum::<usize>(),
+        ));
+    for pool in Pool::ALL {
+        let mut owned: Vec<_> = counts
+            .values()
+            .filter(|(product, _)| product.pool == pool)
+            .collect();
+        if owned.is_empty() {
+            continue;
+        }
+        owned.sort_by_key(|(product, _)| &product.name);
+        let in_pool = data
+            .products
+            .iter()
+            .filter(|product| product.pool == pool)
+            .count();
+        let collected = data
+            .products
+            .iter()
+            .filter(|product| product.pool == pool && counts.contains_key(product.sku.as_str()))
+            .count();
+
+        let value = field_value(
+            owned
+                .iter()
+                .map(|(product, count)| format!("{} ×{}", product.name, count)),
+        );
+        embed = embed.field(format!("{pool} ({collected}/{in_pool})"), value, true);
+    }
+    if let Some(catalogue) = &data.products.daily
+        && !daily.is_empty()
+    {
+        let mut owned: Vec<_> = daily.values().collect();
+        owned.sort_by_key(|(product, _)| &product.name);
+        let collected = catalogue
+            .products
+            .iter()
+            .filter(|product| daily.contains_key(product.sku.as_str()))
+            .count();
+        let value = field_value(
+            owned
+                .iter()
+                .map(|(product, count)| format!("{} ×{}", product.name, count)),
+        );
+        embed = embed.field(
+            format!("Daily ({collected}/{})", catalogue.products.iter().count()),
+            value,
+            false,
+        );
+    }
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Counts how many of each product there are.
+fn count<'a>(
+    products: impl Iterator<Item = &'a Product>,
+) -> HashMap<&'a str, (&'a Product, usize)> {
+    let mut counts = HashMap::<&str, (&Product, usize)>::new();
+    for product in products {
+        counts.entry(&product.sku).or_insert((product, 0)).1 += 1;
+    }
+    counts
+}
@@ -0,0 +1,36 @@
+use crate::database::{PullsData, RecordId, Row};
+use crate::{Context, CustomError, Error};
+
+/// Claim your daily free summon.
+#[poise::command(slash_command, ephemeral, guild_only)]
+pub async fn daily(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let user = ctx.author();
+    let guild_id = ctx.guild_id().unwrap();
+    let daily = data
+        .products
+        .daily
+        .as_ref()
+        .filter(|daily| daily.guilds.contains(&guild_id))
+        .ok_or_else(|| CustomError("Daily summons are not available in this server.".to_owned()))?;
+
+    let id = RecordId::Daily(user.id);
+    let _guard = data.lock.lock().await;
+    let mut row = data.sheets.get_record(id).await?.unwrap_or_else(|| {
+        Row::new(
+            id,
+            user.id.to_string(),
+            user.name.to_owned(),
+            PullsData::new(0, 0),
+        )
+    });
+    row.pulls.claim_daily(guild_id, daily.reset_hours)?;
+    log::info!("{} claimed a daily summon in {}", user.name, guild_id);
+
+    row.pulls.commit_seed(&mut *data.rng.lock().unwrap());
+    let profile = data.sheets.get_profile(user).await?;
+    let message = row.pulls.to_message(id, data.wallet(&profile), None)?;
+    ctx.send(message.into_reply()).await?;
+    data.sheets.save(row).await?;
+    Ok(())
+}
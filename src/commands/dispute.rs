@@ -0,0 +1,161 @@
+use super::{CompleteSets, SetChanges};
+use crate::database::RecordId;
+use crate::shopify::OrderNumber;
+use crate::{Action, Context, CustomError, Data, Error, InteractionType, staff};
+use poise::serenity_prelude::{self as serenity, *};
+
+/// Ask staff to look into an order which someone else has already summoned for.
+#[poise::command(slash_command, ephemeral)]
+pub async fn dispute(
+    ctx: Context<'_>,
+    #[description = "Order Number"] order_number: OrderNumber,
+    #[description = "Anything staff should know, such as the name or email on the order"]
+    reason: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let user = ctx.author();
+    let id = RecordId::Order(order_number);
+    let Some(row) = data.sheets.get_record(id).await? else {
+        ctx.say("Nobody has summoned for this order yet. Use `/summon` to claim it.")
+            .await?;
+        return Ok(());
+    };
+    if row.discord_user_id == user.id.to_string() {
+        ctx.say("This order is already yours.").await?;
+        return Ok(());
+    }
+
+    let button = |approve: bool| {
+        CreateButton::new(
+            serde_json::to_string(&InteractionType {
+                id,
+                action: Action::Resolve {
+                    claimant: user.id,
+                    approve,
+                },
+            })
+            .unwrap(),
+        )
+    };
+    let message = CreateMessage::new()
+        .content(format!(
+            "<@{}> is disputing the claim on {} by <@{}>:\n> {}",
+            user.id,
+            id.label(),
+            row.discord_user_id,
+            reason.replace('\n', "\n> "),
+        ))
+        .components(vec![CreateActionRow::Buttons(vec![
+            button(true).label("Approve").style(ButtonStyle::Success),
+            button(false).label("Deny").style(ButtonStyle::Danger),
+        ])]);
+    data.staff.send(ctx.http(), message).await?;
+    log::info!("{} disputed the claim on {}", user.name, id);
+    ctx.say("Your dispute has been sent to staff, who will look into it and let you know.")
+        .await?;
+    Ok(())
+}
+
+/// Handles staff approving or denying a dispute, from the buttons on its message.
+pub async fn resolve_dispute(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    data: &Data,
+    id: RecordId,
+    claimant: UserId,
+    approve: bool,
+) -> Result<(), Error> {
+    if !data.staff.is_staff(interaction.member.as_ref()) {
+        return Err(CustomError("Only staff may resolve disputes.".to_owned()).into());
+    }
+    let by = interaction.user.name.to_owned();
+    if approve {
+        let user = claimant.to_user(ctx).await?;
+        let (_, changes) = move_record(data, id, &user, by.clone(), "Dispute approved".to_owned())
+            .await?
+            .ok_or_else(|| CustomError(format!("There is no record for {id}.")))?;
+        changes.apply(ctx, interaction.guild_id, None).await;
+    }
+    let outcome = if approve { "approved" } else { "denied" };
+    log::info!("{} {} the dispute by {} over {}", by, outcome, claimant, id);
+
+    let response = EditInteractionResponse::new()
+        .content(format!(
+            "{}\n**{} by {}.**",
+            interaction.message.content,
+            if approve { "Approved" } else { "Denied" },
+            by
+        ))
+        .components(vec![]);
+    interaction.edit_response(&ctx.http, response).await?;
+
+    let message = CreateMessage::new().content(format!(
+        "Your dispute over {} has been {outcome} by staff.",
+        id.label()
+    ));
+    if let Err(error) = claimant.direct_message(ctx, message).await {
+        log::warn!("Failed to tell {} about their dispute: {}", claimant, error);
+    }
+    Ok(())
+}
+
+/// Reassign an order's summons to another user. Staff only.
+#[poise::command(slash_command, ephemeral, guild_only)]
+pub async fn transfer(
+    ctx: Context<'_>,
+    #[description = "Order Number"] order_number: OrderNumber,
+    #[description = "The user to give the order to"] user: User,
+    #[description = "Why the order is being transferred"] reason: String,
+) -> Result<(), Error> {
+    staff::check(ctx).await?;
+    let data = ctx.data();
+    let id = RecordId::Order(order_number);
+
+    let by = ctx.author().name.to_owned();
+    let Some((from, changes)) = move_record(data, id, &user, by, reason.clone()).await? else {
+        ctx.say("Nobody has summoned for this order yet.").await?;
+        return Ok(());
+    };
+    log::info!(
+        "{} transferred {} from {} to {}: {}",
+        ctx.author().name,
+        id,
+        from,
+        user.name,
+        reason
+    );
+    changes
+        .apply(ctx.serenity_context(), ctx.guild_id(), None)
+        .await;
+    ctx.say(format!(
+        "Transferred {} from <@{from}> to <@{}>.",
+        id.label(),
+        user.id
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Gives a record to another user. Returns who it belonged to before, and the sets which either
+/// user has completed or broken up by the move, or `None` if there is no such record.
+async fn move_record<'a>(
+    data: &'a Data,
+    id: RecordId,
+    user: &User,
+    by: String,
+    reason: String,
+) -> Result<Option<(String, SetChanges<'a>)>, Error> {
+    let _guard = data.lock.lock().await;
+    let mut database = data.sheets.database().await?;
+    let Some(mut row) = database.remove(&id) else {
+        return Ok(None);
+    };
+    let from = row.discord_user_id.clone();
+    let users: Vec<_> = from.parse().ok().into_iter().chain([user.id]).collect();
+    let before = CompleteSets::new(&data.products.set, &users, database.values().chain([&row]));
+    row.transfer(user, by, reason);
+    let changes = before.changes(database.values().chain([&row]));
+    data.sheets.save(row).await?;
+    data.forget_stats();
+    Ok(Some((from, changes)))
+}
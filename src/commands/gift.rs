@@ -0,0 +1,95 @@
+use super::check_owner;
+use crate::database::{Change, PullsData, RecordId, Row};
+use crate::shopify::OrderNumber;
+use crate::{Context, CustomError, Error};
+use poise::serenity_prelude::{CreateMessage, User};
+
+/// Give some of your unused summons to another user, who can use them as bonus summons.
+#[poise::command(slash_command, ephemeral)]
+pub async fn gift(
+    ctx: Context<'_>,
+    #[description = "The user to give summons to"] to: User,
+    #[description = "Single summons to give"] singles: Option<usize>,
+    #[description = "Full summons to give"] bulks: Option<usize>,
+    #[description = "Order Number (leave empty for bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let user = ctx.author();
+    let singles = singles.unwrap_or(0);
+    let bulks = bulks.unwrap_or(0);
+    if singles == 0 && bulks == 0 {
+        return Err(CustomError("Choose some summons to give.".to_owned()).into());
+    }
+    if to.id == user.id || to.bot {
+        return Err(CustomError("You cannot give summons to that user.".to_owned()).into());
+    }
+    let id = order_number
+        .map(RecordId::Order)
+        .unwrap_or(RecordId::User(user.id));
+
+    let _guard = data.lock.lock().await;
+    let Some(mut row) = data.sheets.get_record(id).await? else {
+        ctx.say("There are no summons associated with this order.")
+            .await?;
+        return Ok(());
+    };
+    check_owner(&row, user)?;
+    row.pulls.grant(-(singles as i64), -(bulks as i64))?;
+
+    let recipient_id = RecordId::User(to.id);
+    let existing = data.sheets.get_record(recipient_id).await?;
+    let is_new = existing.is_none();
+    let mut recipient = existing.unwrap_or_else(|| {
+        Row::new(
+            recipient_id,
+            to.id.to_string(),
+            to.name.to_owned(),
+            PullsData::new(0, 0),
+        )
+    });
+    recipient.pulls.grant(singles as i64, bulks as i64)?;
+
+    let change = || Change::Gift {
+        from: row.id.to_string(),
+        to: recipient_id.to_string(),
+        singles,
+        bulks,
+    };
+    let (given, received) = (change(), change());
+    row.pulls.record(user.name.to_owned(), given);
+    recipient.pulls.record(user.name.to_owned(), received);
+    log::info!(
+        "{} gave {} singles and {} bulks from {} to {}",
+        user.name,
+        singles,
+        bulks,
+        id,
+        to.name
+    );
+
+    if is_new {
+        // A new row cannot be saved together with another, so the summons are taken from the
+        // giver before they are given, so that they cannot be duplicated if saving fails partway.
+        data.sheets.save(row).await?;
+        data.sheets.save(recipient).await?;
+    } else {
+        data.sheets.save_all(vec![row, recipient]).await?;
+    }
+
+    let message = CreateMessage::new().content(format!(
+        "<@{}> has given you **{bulks} full summons** and **{singles} single summons**! Use `/summon` without an order number to use them.",
+        user.id
+    ));
+    if let Err(error) = to.direct_message(ctx, message).await {
+        log::warn!("Failed to tell {} about their gift: {}", to.name, error);
+    }
+    ctx.say(format!(
+        "You gave <@{}> **{bulks} full summons** and **{singles} single summons** from {}.",
+        to.id,
+        id.label()
+    ))
+    .await?;
+    Ok(())
+}
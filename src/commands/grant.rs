@@ -0,0 +1,76 @@
+use super::record_id;
+use crate::database::{Change, PullsData, Row};
+use crate::shopify::OrderNumber;
+use crate::{Context, CustomError, Error, staff};
+use poise::serenity_prelude::User;
+
+/// Add or remove summons on an order, or a user's bonus summons. Staff only.
+#[poise::command(slash_command, ephemeral, guild_only)]
+pub async fn grant(
+    ctx: Context<'_>,
+    #[description = "Order Number (leave empty for the user's bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+    #[description = "The user to grant bonus summons to"] user: Option<User>,
+    #[description = "Single summons to add (negative to remove)"] singles: Option<i64>,
+    #[description = "Full summons to add (negative to remove)"] bulks: Option<i64>,
+    #[description = "Why the summons are being granted"] reason: String,
+) -> Result<(), Error> {
+    staff::check(ctx).await?;
+    let data = ctx.data();
+    let singles = singles.unwrap_or(0);
+    let bulks = bulks.unwrap_or(0);
+
+    let id = record_id(order_number, user.as_ref())?;
+
+    let _guard = data.lock.lock().await;
+    let mut row = match (data.sheets.get_record(id).await?, user) {
+        (Some(row), _) => row,
+        (None, Some(user)) if id.order_number().is_none() => Row::new(
+            id,
+            user.id.to_string(),
+            user.name.to_owned(),
+            PullsData::new(0, 0),
+        ),
+        // The summons purchased on an order are only counted when its record is created by
+        // the first summon, so creating it here would lose them.
+        (None, _) => {
+            return Err(CustomError(format!(
+                "Nobody has summoned for {} yet, so its purchased summons have not been counted. Ask the customer to use `/summon` first, or grant them bonus summons instead.",
+                id.label()
+            ))
+            .into());
+        }
+    };
+    row.pulls.grant(singles, bulks)?;
+    row.pulls.record(
+        ctx.author().name.to_owned(),
+        Change::Grant {
+            singles,
+            bulks,
+            reason: reason.clone(),
+        },
+    );
+    log::info!(
+        "{} granted {} singles and {} bulks to {}: {}",
+        ctx.author().name,
+        singles,
+        bulks,
+        row.id,
+        reason
+    );
+    let message = format!(
+        "Granted {singles} single summons and {bulks} full summons to <@{}> on {}. They now have {} single and {} full summons in total.",
+        row.discord_user_id,
+        if order_number.is_some() {
+            row.id.label()
+        } else {
+            "their bonus summons".to_owned()
+        },
+        row.pulls.singles,
+        row.pulls.bulks,
+    );
+    data.sheets.save(row).await?;
+    ctx.say(message).await?;
+    Ok(())
+}
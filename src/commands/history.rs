@@ -0,0 +1,26 @@
+use super::{check_owner, own_record_id};
+use crate::shopify::OrderNumber;
+use crate::{Context, Error};
+
+/// Look back through every banner summoned on an order.
+#[poise::command(slash_command, ephemeral)]
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "Order Number (leave empty for bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+    #[description = "Your daily summons instead of an order"] daily: Option<bool>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let id = own_record_id(order_number, daily, ctx.author())?;
+    let Some(row) = data.sheets.get_record(id).await? else {
+        ctx.say("There are no summons associated with this order.")
+            .await?;
+        return Ok(());
+    };
+    check_owner(&row, ctx.author())?;
+
+    let message = row.pulls.to_history_message(id, 0)?;
+    ctx.send(message.into_reply()).await?;
+    Ok(())
+}
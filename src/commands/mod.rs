@@ -0,0 +1,192 @@
+use crate::config::Set;
+use crate::database::{RecordId, Row};
+use crate::shopify::OrderNumber;
+use crate::{Context, CustomError, award_set, revoke_set};
+use poise::serenity_prelude::{
+    self as serenity, AutocompleteChoice, ChannelId, GuildId, User, UserId,
+};
+use std::collections::HashSet;
+
+mod admin;
+mod collection;
+mod daily;
+mod dispute;
+mod gift;
+mod grant;
+mod history;
+mod promo;
+mod rates;
+mod stats;
+mod summon;
+mod trade;
+mod verify;
+mod wishlist;
+
+pub use admin::admin;
+pub use collection::collection;
+pub use daily::daily;
+pub use dispute::{dispute, resolve_dispute, transfer};
+pub use gift::gift;
+pub use grant::grant;
+pub use history::history;
+pub use promo::{promo, redeem};
+pub use rates::rates;
+pub use stats::stats;
+pub use summon::summon;
+pub use trade::{Trades, respond_trade, trade};
+pub use verify::verify;
+pub use wishlist::wishlist;
+
+/// Discord allows at most this many characters in an embed field.
+const FIELD_LIMIT: usize = 1024;
+
+/// Joins lines into the value of an embed field, leaving off any which do not fit.
+fn field_value(lines: impl ExactSizeIterator<Item = String>) -> String {
+    let total = lines.len();
+    let mut value = String::new();
+    for (index, line) in lines.enumerate() {
+        let more = format!("…and {} more", total - index);
+        if value.len() + line.len() + 1 + more.len() > FIELD_LIMIT {
+            value.push_str(&more);
+            break;
+        }
+        value.push_str(&line);
+        value.push('\n');
+    }
+    value
+}
+
+/// Suggests products from the catalogue by name.
+async fn autocomplete_product(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+    ctx.data()
+        .products
+        .iter()
+        .filter(|product| product.name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|product| AutocompleteChoice::new(product.name.clone(), product.sku.clone()))
+        .collect()
+}
+
+/// Identifies the record a staff command acts on: an order, or else a user's bonus summons.
+fn record_id(
+    order_number: Option<OrderNumber>,
+    user: Option<&User>,
+) -> Result<RecordId, CustomError> {
+    match (order_number, user) {
+        (Some(order_number), _) => Ok(RecordId::Order(order_number)),
+        (None, Some(user)) => Ok(RecordId::User(user.id)),
+        (None, None) => Err(CustomError(
+            "Specify an order number, or a user for their bonus summons.".to_owned(),
+        )),
+    }
+}
+
+/// Identifies the record a user's own command acts on: an order, their daily summons, or else
+/// their bonus summons.
+fn own_record_id(
+    order_number: Option<OrderNumber>,
+    daily: Option<bool>,
+    user: &User,
+) -> Result<RecordId, CustomError> {
+    match (order_number, daily.unwrap_or(false)) {
+        (Some(_), true) => Err(CustomError(
+            "Choose either an order number or your daily summons, not both.".to_owned(),
+        )),
+        (Some(order_number), false) => Ok(RecordId::Order(order_number)),
+        (None, true) => Ok(RecordId::Daily(user.id)),
+        (None, false) => Ok(RecordId::User(user.id)),
+    }
+}
+
+/// Ensures that the summons on a row belong to the given user.
+pub fn check_owner(row: &Row, user: &User) -> Result<(), CustomError> {
+    if row.discord_user_id != user.id.to_string() {
+        log::warn!("Wrong user {} accessing {}", user.name, row.id);
+        return Err(CustomError(
+            "This order number has already been summoned for by someone else. Are you sure it's yours? If so, use `/dispute` to ask staff to look into it."
+                .to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// The sets which each of some users has every product of, across all of their records.
+struct CompleteSets<'a> {
+    sets: &'a [Set],
+    users: Vec<(UserId, HashSet<&'a str>)>,
+}
+
+impl<'a> CompleteSets<'a> {
+    fn new<'b>(
+        sets: &'a [Set],
+        users: &[UserId],
+        rows: impl Iterator<Item = &'b Row> + Clone,
+    ) -> Self {
+        let users = users
+            .iter()
+            .map(|&user| (user, Self::of(sets, rows.clone(), user)))
+            .collect();
+        Self { sets, users }
+    }
+
+    fn of<'b>(
+        sets: &'a [Set],
+        rows: impl Iterator<Item = &'b Row>,
+        user: UserId,
+    ) -> HashSet<&'a str> {
+        let id = user.to_string();
+        let owned: HashSet<_> = rows
+            .filter(|row| row.discord_user_id == id)
+            .flat_map(|row| row.pulls.owned_skus())
+            .collect();
+        sets.iter()
+            .filter(|set| set.is_complete(&owned))
+            .map(|set| set.name.as_str())
+            .collect()
+    }
+
+    /// Compares against the same users' sets once products have changed hands.
+    fn changes<'b>(self, rows: impl Iterator<Item = &'b Row> + Clone) -> SetChanges<'a> {
+        let mut changes = SetChanges::default();
+        for (user, before) in self.users {
+            let after = Self::of(self.sets, rows.clone(), user);
+            for set in self.sets {
+                match (
+                    before.contains(set.name.as_str()),
+                    after.contains(set.name.as_str()),
+                ) {
+                    (false, true) => changes.completed.push((user, set)),
+                    (true, false) => changes.broken.push((user, set)),
+                    _ => {}
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Sets which moving products between users has given or taken away from them.
+#[derive(Default)]
+pub struct SetChanges<'a> {
+    completed: Vec<(UserId, &'a Set)>,
+    broken: Vec<(UserId, &'a Set)>,
+}
+
+impl SetChanges<'_> {
+    /// Gives and takes away the roles for each set, announcing completed sets in the channel if
+    /// given.
+    async fn apply(
+        self,
+        ctx: &serenity::Context,
+        guild_id: Option<GuildId>,
+        announce: Option<ChannelId>,
+    ) {
+        for (user, set) in self.completed {
+            award_set(ctx, guild_id, announce, user, set).await;
+        }
+        for (user, set) in self.broken {
+            revoke_set(ctx, guild_id, user, set).await;
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::database::{Promo, PullsData, RecordId, Row};
+use crate::{Context, CustomError, Error, staff};
+use poise::serenity_prelude::Timestamp;
+use rand::distributions::{Alphanumeric, DistString};
+
+/// Create a promo code which grants free summons. Staff only.
+#[poise::command(slash_command, ephemeral, guild_only)]
+pub async fn promo(
+    ctx: Context<'_>,
+    #[description = "Single summons granted"] singles: usize,
+    #[description = "Full summons granted"] bulks: usize,
+    #[description = "Reroll tokens granted"] rerolls: Option<usize>,
+    #[description = "How many users may redeem the code"]
+    #[min = 1]
+    uses: usize,
+    #[description = "Days until the code expires"] expires_in_days: Option<u32>,
+    #[description = "The code (generated if not provided)"] code: Option<String>,
+) -> Result<(), Error> {
+    staff::check(ctx).await?;
+    let data = ctx.data();
+
+    let code = code.unwrap_or_else(|| Alphanumeric.sample_string(&mut rand::thread_rng(), 8));
+    let expires_at = expires_in_days
+        .map(|days| {
+            Timestamp::from_unix_timestamp(
+                Timestamp::now().unix_timestamp() + i64::from(days) * 24 * 60 * 60,
+            )
+        })
+        .transpose()?;
+
+    let _guard = data.lock.lock().await;
+    if data.sheets.get_promo(&code).await?.is_some() {
+        return Err(CustomError(format!("The promo code `{code}` already exists.")).into());
+    }
+    let promo = Promo::new(
+        code,
+        singles,
+        bulks,
+        rerolls.unwrap_or(0),
+        uses,
+        expires_at,
+        ctx.author().name.to_owned(),
+    );
+    log::info!("{} created promo code {:?}", ctx.author().name, promo);
+    let message = format!(
+        "Created promo code `{}` for **{} full summons**, **{} single summons** and **{} rerolls**, redeemable by {} users{}.",
+        promo.code,
+        promo.bulks,
+        promo.singles,
+        promo.rerolls,
+        promo.max_uses,
+        promo
+            .expires_at
+            .map(|expires_at| format!(" until <t:{}:f>", expires_at.unix_timestamp()))
+            .unwrap_or_default(),
+    );
+    data.sheets.save_promo(promo).await?;
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Redeem a promo code for free summons.
+#[poise::command(slash_command, ephemeral)]
+pub async fn redeem(
+    ctx: Context<'_>,
+    #[description = "Promo code"] code: String,
+) -> Result<(), Error> {
+    // Redeeming reads and writes several sheets, which may take longer than Discord waits for
+    // a reply.
+    ctx.defer_ephemeral().await?;
+    let data = ctx.data();
+    let user = ctx.author();
+
+    let _guard = data.lock.lock().await;
+    let mut promo = data
+        .sheets
+        .get_promo(code.trim())
+        .await?
+        .ok_or_else(|| CustomError("That promo code does not exist.".to_owned()))?;
+    promo.redeem(user.id)?;
+    log::info!("{} redeemed promo code {}", user.name, promo.code);
+
+    let id = RecordId::User(user.id);
+    let mut row = data.sheets.get_record(id).await?.unwrap_or_else(|| {
+        Row::new(
+            id,
+            user.id.to_string(),
+            user.name.to_owned(),
+            PullsData::new(0, 0),
+        )
+    });
+    row.pulls.singles += promo.singles;
+    row.pulls.bulks += promo.bulks;
+    let extra = format!(
+        "Redeemed promo code `{}` for **{} full summons**, **{} single summons** and **{} rerolls**!",
+        promo.code, promo.bulks, promo.singles, promo.rerolls
+    );
+    let mut profile = data.sheets.get_profile(user).await?;
+    profile.data.rerolls += promo.rerolls;
+    let wallet = data.wallet(&profile);
+
+    row.pulls.commit_seed(&mut *data.rng.lock().unwrap());
+    let message = row.pulls.to_message(id, wallet, Some(extra))?;
+    // The code is only used up once what it grants has been saved, so that it may be
+    // redeemed again if saving fails.
+    data.sheets.save(row).await?;
+    if promo.rerolls > 0 {
+        data.sheets.save_profile(profile).await?;
+    }
+    data.sheets.save_promo(promo).await?;
+    ctx.send(message.into_reply()).await?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use super::field_value;
+use crate::config::{Pool, Products};
+use crate::{Context, Error, available_inventory, database};
+use poise::CreateReply;
+use poise::serenity_prelude::CreateEmbed;
+use std::collections::HashMap;
+
+/// Show the current chance of summoning each product.
+#[poise::command(slash_command)]
+pub async fn rates(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let database = data.sheets.database().await?;
+    let reserved = database::reserved(database.values());
+    let inventory = available_inventory(data, &reserved).await?;
+
+    let mut embeds = vec![rates_embed(
+        "Summon rates",
+        "Single and full summons are drawn from every pool.",
+        &data.products,
+        &inventory,
+        None,
+    )];
+    if let Some(premium) = &data.products.premium {
+        let pools = premium
+            .pool
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        embeds.push(rates_embed(
+            "Premium summon rates",
+            &format!("Premium summons are drawn only from these pools: {pools}."),
+            &data.products,
+            &inventory,
+            Some(&premium.pool),
+        ));
+    }
+    if let Some(daily) = &data.products.daily {
+        embeds.push(rates_embed(
+            "Daily summon rates",
+            "Daily summons are drawn from their own products, which do not run out.",
+            &daily.products,
+            &daily.products.unlimited_stock(),
+            None,
+        ));
+    }
+
+    // Each catalogue is sent separately, as together they may not fit in one message.
+    for embed in embeds {
+        ctx.send(CreateReply::default().embed(embed)).await?;
+    }
+    Ok(())
+}
+
+fn rates_embed(
+    title: &str,
+    about: &str,
+    products: &Products,
+    inventory: &HashMap<String, usize>,
+    pools: Option<&[Pool]>,
+) -> CreateEmbed {
+    let rates = products.rates(inventory, pools);
+    let embed = CreateEmbed::new().title(title);
+    if rates.is_empty() {
+        return embed.description(format!("{about} They are sold out."));
+    }
+
+    let mut description = format!(
+        "{about} These are the chances of each product appearing in the **first slot** of a banner, based on what is currently in stock. Products are not repeated within a banner while there are others left, so later slots are drawn from the products not yet in it."
+    );
+    if let Some(wishlist) = &products.wishlist {
+        description.push_str(&format!(
+            " Products on your wishlist are {}× as likely to be drawn.",
+            wishlist.boost
+        ));
+    }
+    let sold_out = products
+        .iter()
+        .filter(|product| pools.is_none_or(|pools| pools.contains(&product.pool)))
+        .count()
+        - rates.len();
+    if sold_out > 0 {
+        description.push_str(&format!(" {sold_out} sold out products are not included."));
+    }
+    let mut embed = embed.description(description);
+    for pool in Pool::ALL {
+        let mut in_pool: Vec<_> = rates
+            .iter()
+            .filter(|(product, _)| product.pool == pool)
+            .collect();
+        if in_pool.is_empty() {
+            continue;
+        }
+        in_pool.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        let total: f64 = in_pool.iter().map(|(_, rate)| rate).sum();
+        let value = field_value(
+            in_pool
+                .iter()
+                .map(|(product, rate)| format!("{}: {:.2}%", product.name, rate * 100.0)),
+        );
+        embed = embed.field(format!("{pool} ({:.2}%)", total * 100.0), value, false);
+    }
+    embed
+}
@@ -0,0 +1,71 @@
+use crate::config::Pool;
+use crate::stats::Stats;
+use crate::{Context, Error};
+use poise::CreateReply;
+use poise::serenity_prelude::CreateEmbed;
+
+/// Users need this many pulls to appear among the luckiest.
+const MIN_PULLS: usize = 5;
+
+/// Show the leaderboard and statistics for summons in this server.
+#[poise::command(slash_command, guild_only)]
+pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx.guild_id().unwrap();
+    if !data.stats.lock().unwrap().contains_key(&guild_id) {
+        // Pulls are saved under the lock, and only recorded in the statistics once they are
+        // loaded, so loading them under the lock too means that none are missed.
+        let _guard = data.lock.lock().await;
+        if !data.stats.lock().unwrap().contains_key(&guild_id) {
+            let database = data.sheets.database().await?;
+            let stats = Stats::new(guild_id, &data.products, database.values());
+            data.stats.lock().unwrap().insert(guild_id, stats);
+        }
+    }
+
+    let embed = {
+        let stats = data.stats.lock().unwrap();
+        let stats = &stats[&guild_id];
+        let most_pulled = stats
+            .most_pulled(5)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, pulls))| format!("{}. {name} ×{pulls}", i + 1))
+            .collect::<Vec<_>>();
+        let luckiest = stats
+            .luckiest(5, MIN_PULLS)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (user, rate))| format!("{}. <@{user}>: {:.0}% rare", i + 1, rate * 100.0))
+            .collect::<Vec<_>>();
+        let pools = Pool::ALL
+            .into_iter()
+            .map(|pool| format!("{pool}: {}", stats.pools.get(&pool).copied().unwrap_or(0)))
+            .collect::<Vec<_>>();
+        CreateEmbed::new()
+            .title("Summon statistics")
+            .description(format!(
+                "**{}** products summoned by **{}** users in this server.",
+                stats.summons,
+                stats.users.len()
+            ))
+            .field("Most summoned", or_none(most_pulled), true)
+            .field(
+                format!("Luckiest (at least {MIN_PULLS} summons)"),
+                or_none(luckiest),
+                true,
+            )
+            .field("By pool", pools.join("\n"), true)
+    };
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+fn or_none(lines: Vec<String>) -> String {
+    if lines.is_empty() {
+        "Nobody yet".to_owned()
+    } else {
+        lines.join("\n")
+    }
+}
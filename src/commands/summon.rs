@@ -0,0 +1,143 @@
+use super::check_owner;
+use crate::database::{PullsData, RecordId, Row};
+use crate::shopify::OrderNumber;
+use crate::{Context, Error};
+use poise::serenity_prelude::AutocompleteChoice;
+
+/// Summon enamel pins on Kittyalyst's gacha machine.
+///
+/// Requires a valid purchase order number from https://www.kittyalyst.com/products/kitty-emblem-gacha
+/// Leave the order number out to use your bonus summons instead.
+#[poise::command(slash_command, ephemeral)]
+pub async fn summon(
+    ctx: Context<'_>,
+    #[description = "Order Number (leave empty for bonus summons)"]
+    #[autocomplete = "autocomplete_order"]
+    order_number: Option<OrderNumber>,
+) -> Result<(), Error> {
+    let Context::Application(ctx) = ctx else {
+        unreachable!("Slash command is always application");
+    };
+
+    // Summoning reads the order and several sheets, and may wait on other summons for the lock,
+    // which can take longer than Discord waits for a reply.
+    ctx.defer_ephemeral().await?;
+    let data = ctx.data();
+    let Some(order_number) = order_number else {
+        let id = RecordId::User(ctx.interaction.user.id);
+        let _guard = data.lock.lock().await;
+        let Some(mut row) = data.sheets.get_record(id).await? else {
+            ctx.say("You do not have any bonus summons. Enter the order number of your purchase to summon.")
+                .await?;
+            return Ok(());
+        };
+        row.pulls.commit_seed(&mut *data.rng.lock().unwrap());
+        let profile = data.sheets.get_profile(&ctx.interaction.user).await?;
+        let message = row.pulls.to_message(id, data.wallet(&profile), None)?;
+        ctx.send(message.into_reply()).await?;
+        data.sheets.save(row).await?;
+        return Ok(());
+    };
+
+    log::info!("User attempting pull: {}", order_number);
+    let order = data.shopify.get_order(order_number).await?;
+    log::debug!("Pulling for order: {:#?}", order);
+
+    let _guard = data.lock.lock().await;
+    let row = data.sheets.get_record(order_number).await?;
+    let mut rerolls = 0;
+    let mut row = match row {
+        Some(row) => row,
+        None => {
+            let (singles, bulks, premiums, tokens) = if std::env::var("PULLS_FREE").is_ok() {
+                (3, 2, 0, 0)
+            } else {
+                order
+                    .line_items
+                    .nodes
+                    .iter()
+                    .flat_map(|item| {
+                        data.products
+                            .ticket
+                            .iter()
+                            .filter(|ticket| ticket.matches(item))
+                            .map(|ticket| {
+                                (
+                                    ticket.singles * item.quantity,
+                                    ticket.bulks * item.quantity,
+                                    ticket.premiums * item.quantity,
+                                    ticket.rerolls * item.quantity,
+                                )
+                            })
+                    })
+                    .fold((0, 0, 0, 0), |a, b| {
+                        (a.0 + b.0, a.1 + b.1, a.2 + b.2, a.3 + b.3)
+                    })
+            };
+
+            if singles == 0 && bulks == 0 && premiums == 0 && tokens == 0 {
+                log::warn!("Trying to pull {} with no purchased tickets", order_number);
+                ctx.say("There are no available summons associated with this order. Buy a summon for a valid order number: https://www.kittyalyst.com/products/kitty-emblem-gacha")
+                    .await?;
+                return Ok(());
+            } else {
+                rerolls = tokens;
+                let mut pulls = PullsData::new(singles, bulks);
+                pulls.premiums = premiums;
+                Row::new(
+                    order_number.into(),
+                    ctx.interaction.user.id.to_string(),
+                    ctx.interaction.user.name.to_owned(),
+                    pulls,
+                )
+            }
+        }
+    };
+
+    log::debug!("Pull state: {:#?}", row);
+
+    check_owner(&row, &ctx.interaction.user)?;
+    row.pulls.commit_seed(&mut *data.rng.lock().unwrap());
+    let mut profile = data.sheets.get_profile(&ctx.interaction.user).await?;
+    profile.data.rerolls += rerolls;
+    let message = row.pulls.to_message(row.id, data.wallet(&profile), None)?;
+    // The order's rerolls are only granted when its row is first saved, so the row is saved
+    // first so that they cannot be granted twice.
+    data.sheets.save(row).await?;
+    if rerolls > 0 {
+        data.sheets.save_profile(profile).await?;
+    }
+    ctx.send(message.into_reply()).await?;
+
+    Ok(())
+}
+
+/// Suggests the orders the user has already summoned for, newest first, along with how many
+/// summons they have left on each.
+async fn autocomplete_order(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let user = ctx.author().id.to_string();
+    let Ok(database) = ctx.data().sheets.database().await else {
+        return vec![];
+    };
+    let partial = partial.trim().trim_start_matches('#');
+    let mut orders: Vec<_> = database
+        .values()
+        .filter(|row| row.discord_user_id == user)
+        .filter_map(|row| Some((row.id.order_number()?, row)))
+        .filter(|(order_number, _)| order_number.to_string()[1..].starts_with(partial))
+        .collect();
+    orders.sort_by_key(|(order_number, _)| std::cmp::Reverse(*order_number));
+    orders
+        .into_iter()
+        .take(25)
+        .map(|(order_number, row)| {
+            let (bulks, singles, premiums) = row.pulls.remaining();
+            AutocompleteChoice::new(
+                format!(
+                    "{order_number}: {bulks} full, {singles} single and {premiums} premium summons left"
+                ),
+                order_number.to_string(),
+            )
+        })
+        .collect()
+}
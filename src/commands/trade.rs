@@ -0,0 +1,301 @@
+use super::autocomplete_product;
+use super::{CompleteSets, SetChanges};
+use crate::config::Product;
+use crate::database::{Change, RecordId, Row};
+use crate::{Action, Context, CustomError, Data, Error, InteractionType};
+use poise::serenity_prelude::{self as serenity, *};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a proposed trade may wait to be confirmed, as the products it names may have
+/// changed hands since.
+const EXPIRY: Duration = Duration::from_secs(15 * 60);
+
+/// Trades which are waiting to be confirmed, by a short key which fits in a button's custom ID.
+#[derive(Debug)]
+pub struct Trades {
+    /// Starts at random, so that buttons left from before a restart do not match new trades.
+    next: u32,
+    pending: HashMap<u32, Trade>,
+}
+
+impl Default for Trades {
+    fn default() -> Self {
+        Self {
+            next: rand::random(),
+            pending: HashMap::new(),
+        }
+    }
+}
+
+impl Trades {
+    fn insert(&mut self, trade: Trade) -> u32 {
+        self.prune();
+        let key = self.next;
+        self.next = self.next.wrapping_add(1);
+        self.pending.insert(key, trade);
+        key
+    }
+
+    /// Forgets trades which have waited too long to be confirmed.
+    fn prune(&mut self) {
+        self.pending
+            .retain(|_, trade| trade.proposed_at.elapsed() < EXPIRY);
+    }
+}
+
+/// A proposed trade, waiting for both users to confirm it.
+#[derive(Debug)]
+pub struct Trade {
+    proposer: UserId,
+    recipient: UserId,
+    give: Product,
+    want: Product,
+    confirmed: Vec<UserId>,
+    proposed_at: Instant,
+}
+
+impl Trade {
+    fn message(&self, status: &str) -> String {
+        format!(
+            "<@{}> offers their **{}** for <@{}>'s **{}**.\n{}",
+            self.proposer, self.give.name, self.recipient, self.want.name, status
+        )
+    }
+}
+
+/// Offer to trade one of your summoned products for one of another user's.
+#[poise::command(slash_command, guild_only)]
+pub async fn trade(
+    ctx: Context<'_>,
+    #[description = "The user to trade with"] with: User,
+    #[description = "The product you will give"]
+    #[autocomplete = "autocomplete_owned"]
+    give: String,
+    #[description = "The product you want in return"]
+    #[autocomplete = "autocomplete_product"]
+    want: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let user = ctx.author();
+    if with.id == user.id || with.bot {
+        return Err(CustomError("You cannot trade with that user.".to_owned()).into());
+    }
+    let find = |query: &str| {
+        data.products
+            .find(query.trim())
+            .cloned()
+            .ok_or_else(|| CustomError(format!("There is no product called {query}.")))
+    };
+    let give = find(&give)?;
+    let want = find(&want)?;
+
+    let database = data.sheets.database().await?;
+    if holder(&database, user.id, &give.sku).is_none() {
+        return Err(CustomError(format!("You do not have a **{}** to trade.", give.name)).into());
+    }
+    if holder(&database, with.id, &want.sku).is_none() {
+        return Err(CustomError(format!(
+            "<@{}> does not have a **{}** to trade.",
+            with.id, want.name
+        ))
+        .into());
+    }
+
+    let trade = Trade {
+        proposer: user.id,
+        recipient: with.id,
+        give,
+        want,
+        confirmed: vec![],
+        proposed_at: Instant::now(),
+    };
+    let content = trade.message(&format!(
+        "Both of you must confirm the trade within {} minutes.",
+        EXPIRY.as_secs() / 60
+    ));
+    log::info!("{} proposed a trade: {:?}", user.name, trade);
+    let key = data.trades.lock().unwrap().insert(trade);
+    ctx.send(
+        poise::CreateReply::default()
+            .content(content)
+            .components(buttons(user.id, key)),
+    )
+    .await?;
+    Ok(())
+}
+
+fn buttons(proposer: UserId, trade: u32) -> Vec<CreateActionRow> {
+    let button = |confirm: bool| CreateButton::new(custom_id(proposer, trade, confirm));
+    vec![CreateActionRow::Buttons(vec![
+        button(true).label("Confirm").style(ButtonStyle::Success),
+        button(false).label("Cancel").style(ButtonStyle::Danger),
+    ])]
+}
+
+fn custom_id(proposer: UserId, trade: u32, confirm: bool) -> String {
+    serde_json::to_string(&InteractionType {
+        id: RecordId::User(proposer),
+        action: Action::Trade { trade, confirm },
+    })
+    .unwrap()
+}
+
+/// The record of a user's which a product would be traded from: an order it ships with.
+fn holder(database: &HashMap<RecordId, Row>, user: UserId, sku: &str) -> Option<RecordId> {
+    let user = user.to_string();
+    let mut ids: Vec<_> = database
+        .values()
+        .filter(|row| row.discord_user_id == user && row.id.tracks_inventory())
+        .filter(|row| row.pulls.owned_skus().any(|owned| owned == sku))
+        .map(|row| row.id)
+        .collect();
+    ids.sort_by_key(|id| id.to_string());
+    ids.into_iter().next()
+}
+
+/// Handles either user confirming or cancelling a trade, from the buttons on its message. Once
+/// both have confirmed, the products are exchanged.
+pub async fn respond_trade(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    data: &Data,
+    id: RecordId,
+    key: u32,
+    confirm: bool,
+) -> Result<(), Error> {
+    let user = interaction.user.id;
+    let pending = {
+        let mut trades = data.trades.lock().unwrap();
+        trades.prune();
+        let trade = trades
+            .pending
+            .get_mut(&key)
+            .filter(|trade| id == RecordId::User(trade.proposer))
+            .ok_or_else(|| {
+                CustomError(format!(
+                    "This trade is no longer available. Trades expire after {} minutes.",
+                    EXPIRY.as_secs() / 60
+                ))
+            })?;
+        if user != trade.proposer && user != trade.recipient {
+            return Err(CustomError("This trade is not yours.".to_owned()).into());
+        }
+        if confirm && !trade.confirmed.contains(&user) {
+            trade.confirmed.push(user);
+        }
+        if confirm && trade.confirmed.len() < 2 {
+            Ok(trade.message(&format!(
+                "<@{user}> has confirmed. Waiting for the other user to confirm."
+            )))
+        } else {
+            Err(trades.pending.remove(&key).unwrap())
+        }
+    };
+
+    let mut changes = SetChanges::default();
+    let response = match pending {
+        Ok(content) => EditInteractionResponse::new().content(content),
+        Err(trade) => {
+            let content = if !confirm {
+                log::info!("{} cancelled trade {:?}", interaction.user.name, trade);
+                trade.message(&format!("**Cancelled by <@{user}>.**"))
+            } else {
+                match exchange(ctx, data, &trade).await {
+                    Err(error) => {
+                        log::warn!("Trade {:?} failed: {}", trade, error);
+                        trade.message(&format!("**The trade could not be completed:** {error}"))
+                    }
+                    Ok(sets) => {
+                        log::info!("Trade completed: {:?}", trade);
+                        changes = sets;
+                        trade.message("**The trade is complete!**")
+                    }
+                }
+            };
+            EditInteractionResponse::new()
+                .content(content)
+                .components(vec![])
+        }
+    };
+    interaction.edit_response(&ctx.http, response).await?;
+    changes
+        .apply(ctx, interaction.guild_id, Some(interaction.channel_id))
+        .await;
+    Ok(())
+}
+
+/// Moves each product to the other user's order, saving both at once. Returns the sets which
+/// either user has completed or broken up by doing so.
+async fn exchange<'a>(
+    ctx: &serenity::Context,
+    data: &'a Data,
+    trade: &Trade,
+) -> Result<SetChanges<'a>, Error> {
+    let _guard = data.lock.lock().await;
+    let mut database = data.sheets.database().await?;
+    let users = [trade.proposer, trade.recipient];
+    let before = CompleteSets::new(&data.products.set, &users, database.values());
+    let missing = || CustomError("A product in this trade is no longer owned.".to_owned());
+    let from = holder(&database, trade.proposer, &trade.give.sku).ok_or_else(missing)?;
+    let to = holder(&database, trade.recipient, &trade.want.sku).ok_or_else(missing)?;
+    let mut from = database.remove(&from).unwrap();
+    let mut to = database.remove(&to).unwrap();
+
+    from.pulls.trade(&trade.give.sku, trade.want.clone())?;
+    to.pulls.trade(&trade.want.sku, trade.give.clone())?;
+    from.pulls.record(
+        from.discord_username.clone(),
+        Change::Trade {
+            gave: trade.give.sku.clone(),
+            received: trade.want.sku.clone(),
+            with: to.discord_user_id.clone(),
+            record: to.id.to_string(),
+        },
+    );
+    to.pulls.record(
+        to.discord_username.clone(),
+        Change::Trade {
+            gave: trade.want.sku.clone(),
+            received: trade.give.sku.clone(),
+            with: from.discord_user_id.clone(),
+            record: from.id.to_string(),
+        },
+    );
+    let message = format!(
+        "Trade: {} now ships {} instead of {}, and {} now ships {} instead of {}.",
+        from.id.label(),
+        trade.want.sku,
+        trade.give.sku,
+        to.id.label(),
+        trade.give.sku,
+        trade.want.sku,
+    );
+    let changes = before.changes(database.values().chain([&from, &to]));
+    data.sheets.save_all(vec![from, to]).await?;
+    data.staff.alert(&ctx.http, message).await;
+
+    Ok(changes)
+}
+
+async fn autocomplete_owned(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let user = ctx.author().id.to_string();
+    let Ok(database) = ctx.data().sheets.database().await else {
+        return vec![];
+    };
+    let partial = partial.to_lowercase();
+    let mut owned: Vec<_> = database
+        .values()
+        .filter(|row| row.discord_user_id == user && row.id.tracks_inventory())
+        .flat_map(|row| row.pulls.owned_products())
+        .filter(|product| product.name.to_lowercase().contains(&partial))
+        .map(|product| (product.name.clone(), product.sku.clone()))
+        .collect();
+    owned.sort();
+    owned.dedup();
+    owned
+        .into_iter()
+        .take(25)
+        .map(|(name, sku)| AutocompleteChoice::new(name, sku))
+        .collect()
+}
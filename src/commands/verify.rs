@@ -0,0 +1,43 @@
+use super::{check_owner, own_record_id};
+use crate::shopify::OrderNumber;
+use crate::{Context, Error};
+use poise::CreateReply;
+use poise::serenity_prelude::CreateAttachment;
+
+/// Reveal the server seed for an order's summons, and check that they were fair.
+///
+/// Any summons made afterwards will use a new server seed. Everything needed to reproduce the
+/// summons independently is attached.
+#[poise::command(slash_command, ephemeral)]
+pub async fn verify(
+    ctx: Context<'_>,
+    #[description = "Order Number (leave empty for bonus summons)"] order_number: Option<
+        OrderNumber,
+    >,
+    #[description = "Your daily summons instead of an order"] daily: Option<bool>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let id = own_record_id(order_number, daily, ctx.author())?;
+    let _guard = data.lock.lock().await;
+    let Some(mut row) = data.sheets.get_record(id).await? else {
+        ctx.say("There are no summons associated with this order.")
+            .await?;
+        return Ok(());
+    };
+    check_owner(&row, ctx.author())?;
+
+    let products = data.catalogue(id)?;
+    row.pulls.reveal_seed(&mut *data.rng.lock().unwrap())?;
+    let proofs = serde_json::to_string_pretty(&row.pulls.proofs(id, products))?;
+    let message = row.pulls.verification(id, products)?;
+    // The seed is saved as revealed first, so that it is not revealed without being recorded
+    // if the reply fails.
+    data.sheets.save(row).await?;
+    ctx.send(
+        CreateReply::default()
+            .content(message)
+            .attachment(CreateAttachment::bytes(proofs, "verification.json")),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use super::autocomplete_product;
+use crate::config::{Products, Wishlist};
+use crate::database::Profile;
+use crate::{Context, CustomError, Error};
+use poise::serenity_prelude::AutocompleteChoice;
+
+/// Manage the products you are hoping to summon.
+#[poise::command(
+    slash_command,
+    ephemeral,
+    subcommands("add", "remove", "show"),
+    subcommand_required
+)]
+pub async fn wishlist(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a product to your wishlist, making it more likely to appear in your summons.
+#[poise::command(slash_command, ephemeral)]
+async fn add(
+    ctx: Context<'_>,
+    #[description = "Product name"]
+    #[autocomplete = "autocomplete_product"]
+    product: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let config = config(ctx)?;
+    let product = data
+        .products
+        .find(product.trim())
+        .ok_or_else(|| CustomError(format!("There is no product called {product}.")))?;
+
+    let _guard = data.lock.lock().await;
+    let mut profile = data.sheets.get_profile(ctx.author()).await?;
+    if profile.data.wishlist.contains(&product.sku) {
+        return Err(CustomError(format!("{} is already on your wishlist.", product.name)).into());
+    }
+    if profile.data.wishlist.len() >= config.size {
+        return Err(CustomError(format!(
+            "Your wishlist is full. You may wish for at most {} products.",
+            config.size
+        ))
+        .into());
+    }
+    profile.data.wishlist.push(product.sku.clone());
+    log::info!("{} wishlisted {}", ctx.author().name, product.sku);
+    let message = format!(
+        "Added {} to your wishlist.\n{}",
+        product.name,
+        show_wishlist(ctx, &profile)
+    );
+    data.sheets.save_profile(profile).await?;
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Remove a product from your wishlist.
+#[poise::command(slash_command, ephemeral)]
+async fn remove(
+    ctx: Context<'_>,
+    #[description = "Product name"]
+    #[autocomplete = "autocomplete_wishlisted"]
+    product: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    config(ctx)?;
+    // Products may have been removed from the catalogue since they were wishlisted, so they
+    // are matched by the SKU stored on the wishlist.
+    let query = product.trim();
+    let sku = data
+        .products
+        .find(query)
+        .map_or(query, |product| product.sku.as_str());
+
+    let _guard = data.lock.lock().await;
+    let mut profile = data.sheets.get_profile(ctx.author()).await?;
+    let Some(index) = profile
+        .data
+        .wishlist
+        .iter()
+        .position(|wishlisted| wishlisted.eq_ignore_ascii_case(sku))
+    else {
+        return Err(CustomError(format!("{query} is not on your wishlist.")).into());
+    };
+    let sku = profile.data.wishlist.remove(index);
+    log::info!("{} unwishlisted {}", ctx.author().name, sku);
+    let message = format!(
+        "Removed {} from your wishlist.\n{}",
+        product_name(&data.products, &sku),
+        show_wishlist(ctx, &profile)
+    );
+    data.sheets.save_profile(profile).await?;
+    ctx.say(message).await?;
+    Ok(())
+}
+
+async fn autocomplete_wishlisted(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Ok(profile) = ctx.data().sheets.get_profile(ctx.author()).await else {
+        return vec![];
+    };
+    let partial = partial.to_lowercase();
+    profile
+        .data
+        .wishlist
+        .into_iter()
+        .map(|sku| (product_name(&ctx.data().products, &sku).to_owned(), sku))
+        .filter(|(name, _)| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|(name, sku)| AutocompleteChoice::new(name, sku))
+        .collect()
+}
+
+/// Show the products on your wishlist.
+#[poise::command(slash_command, ephemeral)]
+async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    config(ctx)?;
+    let profile = ctx.data().sheets.get_profile(ctx.author()).await?;
+    ctx.say(show_wishlist(ctx, &profile)).await?;
+    Ok(())
+}
+
+fn config(ctx: Context<'_>) -> Result<&Wishlist, CustomError> {
+    ctx.data()
+        .products
+        .wishlist
+        .as_ref()
+        .ok_or_else(|| CustomError("Wishlists are not enabled.".to_owned()))
+}
+
+fn show_wishlist(ctx: Context<'_>, profile: &Profile) -> String {
+    let products = &ctx.data().products;
+    if profile.data.wishlist.is_empty() {
+        return "Your wishlist is empty.".to_owned();
+    }
+    let names = profile
+        .data
+        .wishlist
+        .iter()
+        .map(|sku| format!("- {}", product_name(products, sku)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Your wishlist ({}/{}):\n{names}",
+        profile.data.wishlist.len(),
+        products
+            .wishlist
+            .as_ref()
+            .map_or(0, |wishlist| wishlist.size),
+    )
+}
+
+/// The name of a wishlisted product, or its SKU if it is no longer in the catalogue.
+fn product_name<'a>(products: &'a Products, sku: &'a str) -> &'a str {
+    products
+        .iter()
+        .find(|product| product.sku == sku)
+        .map_or(sku, |product| product.name.as_str())
+}
@@ -1,11 +1,20 @@
-use super::{Pool, Product};
+use super::{Pool, Product, Products};
 use image::imageops::overlay;
 use image::{ImageError, ImageFormat, load, load_from_memory_with_format};
+use poise::serenity_prelude::{GuildId, Timestamp};
+use rand::distributions::Distribution as _;
+use rand::distributions::weighted::WeightedIndex;
+use rand::prelude::*;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
 
+/// The number of slots in a banner.
+pub const SLOTS: usize = 5;
+
 const SUMMON_PNG: &[u8] = include_bytes!("../../assets/summon.png");
 const RED_PNG: &[u8] = include_bytes!("../../assets/red.png");
 const BLUE_PNG: &[u8] = include_bytes!("../../assets/blue.png");
@@ -16,6 +25,141 @@ const WHITE_PNG: &[u8] = include_bytes!("../../assets/grey.png");
 pub struct Banner {
     pub slots: [Product; 5],
     pub revealed: [bool; 5],
+    /// When this banner was created or last had a slot revealed. Banners from before this
+    /// was recorded have no value.
+    #[serde(default)]
+    pub updated_at: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Proof>,
+    /// The server this banner was summoned in, which its pulls count towards in `/stats`.
+    /// Banners from before this was recorded have no value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+}
+
+/// Everything needed, along with the order's server seed and the catalogue, to reproduce a
+/// banner.
+///
+/// To keep records small, only the candidates which differ from their product in the catalogue
+/// are stored, along with a hash of every candidate, which shows whether the catalogue has
+/// since changed in a way which affects them.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Proof {
+    /// The index of the server seed among the order's seeds.
+    pub seed: usize,
+    pub nonce: usize,
+    /// The SHA-256 hash of the candidates as JSON.
+    pub hash: String,
+    /// The allowance and weight of each candidate which is limited to fewer than every slot,
+    /// or which is weighted other than by its rarity.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub adjusted: BTreeMap<String, (usize, f64)>,
+    /// The SKUs of the products in the catalogue which could not be drawn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<String>,
+}
+
+impl Proof {
+    pub fn new(seed: usize, nonce: usize, products: &Products, candidates: &[Candidate]) -> Self {
+        let adjusted = candidates
+            .iter()
+            .filter(|candidate| {
+                candidate.allowance < SLOTS
+                    || products
+                        .iter()
+                        .find(|product| product.sku == candidate.sku)
+                        .is_none_or(|product| product.rarity != candidate.weight)
+            })
+            .map(|candidate| {
+                (
+                    candidate.sku.clone(),
+                    (candidate.allowance, candidate.weight),
+                )
+            })
+            .collect();
+        let excluded = products
+            .iter()
+            .filter(|product| {
+                !candidates
+                    .iter()
+                    .any(|candidate| candidate.sku == product.sku)
+            })
+            .map(|product| product.sku.clone())
+            .collect();
+        Self {
+            seed,
+            nonce,
+            hash: hash(candidates),
+            adjusted,
+            excluded,
+        }
+    }
+
+    /// The products the banner was drawn from, worked out again from the catalogue, or `None`
+    /// if the catalogue has changed since in a way which affects them.
+    pub fn candidates(&self, products: &Products) -> Option<Vec<Candidate>> {
+        let candidates: Vec<_> = products
+            .iter()
+            .filter(|product| !self.excluded.contains(&product.sku))
+            .map(|product| {
+                let (allowance, weight) = self
+                    .adjusted
+                    .get(&product.sku)
+                    .copied()
+                    .unwrap_or((SLOTS, product.rarity));
+                Candidate {
+                    sku: product.sku.clone(),
+                    allowance,
+                    weight,
+                }
+            })
+            .collect();
+        (hash(&candidates) == self.hash).then_some(candidates)
+    }
+}
+
+fn hash(candidates: &[Candidate]) -> String {
+    let json = serde_json::to_string(candidates).unwrap();
+    format!("{:x}", Sha256::digest(json))
+}
+
+/// A product which may be drawn for a banner.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Candidate {
+    pub sku: String,
+    /// The most times the product may appear in the banner, which is at most [`SLOTS`].
+    pub allowance: usize,
+    /// The relative chance of drawing the product, including any wishlist boost.
+    pub weight: f64,
+}
+
+/// Draws the SKUs for the 5 slots of a banner. For each slot, one candidate is chosen with
+/// `rand` 0.8's `WeightedIndex` from those which have not yet been drawn, or if there are none
+/// left, from those which have been drawn fewer times than their allowance.
+///
+/// Returns `None` if there are not enough units allowed to fill a banner.
+pub fn draw(candidates: &[Candidate], rng: &mut impl Rng) -> Option<[String; 5]> {
+    let mut chosen = HashMap::<&str, usize>::new();
+    let mut skus = Vec::with_capacity(5);
+    for _ in 0..5 {
+        let available: Vec<_> = candidates
+            .iter()
+            .filter(|candidate| {
+                candidate.allowance > chosen.get(candidate.sku.as_str()).copied().unwrap_or(0)
+            })
+            .collect();
+        let fresh: Vec<_> = available
+            .iter()
+            .copied()
+            .filter(|candidate| !chosen.contains_key(candidate.sku.as_str()))
+            .collect();
+        let pool = if fresh.is_empty() { available } else { fresh };
+        let weighted = WeightedIndex::new(pool.iter().map(|candidate| candidate.weight)).ok()?;
+        let candidate = pool[weighted.sample(rng)];
+        *chosen.entry(&candidate.sku).or_default() += 1;
+        skus.push(candidate.sku.clone());
+    }
+    skus.try_into().ok()
 }
 
 impl Pool {
@@ -37,9 +181,31 @@ impl Banner {
         Self {
             slots,
             revealed: [false; 5],
+            updated_at: Some(Timestamp::now()),
+            proof: None,
+            guild_id: None,
         }
     }
 
+    pub fn reveal(&mut self, slot: usize) {
+        self.revealed[slot] = true;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    /// Undoes the reveal of a slot.
+    pub fn void(&mut self, slot: usize) {
+        self.revealed[slot] = false;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub fn unrevealed_products(&self) -> impl Iterator<Item = &Product> + '_ {
+        self.revealed
+            .into_iter()
+            .zip(&self.slots)
+            .filter(|(revealed, _)| !*revealed)
+            .map(|(_, slot)| slot)
+    }
+
     pub fn pulled(&self) -> usize {
         self.revealed
             .into_iter()
@@ -101,3 +267,86 @@ impl Banner {
         Ok(bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+
+    fn candidates(allowances: &[usize]) -> Vec<Candidate> {
+        allowances
+            .iter()
+            .enumerate()
+            .map(|(i, allowance)| Candidate {
+                sku: format!("SKU-{i}"),
+                allowance: *allowance,
+                weight: (i + 1) as f64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_are_reproducible_from_the_rng() {
+        let candidates = candidates(&[1; 8]);
+        let a = draw(&candidates, &mut ChaCha20Rng::seed_from_u64(7)).unwrap();
+        let b = draw(&candidates, &mut ChaCha20Rng::seed_from_u64(7)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, ["SKU-0", "SKU-5", "SKU-7", "SKU-3", "SKU-2"]);
+    }
+
+    #[test]
+    fn draws_repeat_products_only_once_every_product_is_drawn() {
+        let candidates = candidates(&[2, 1, 2]);
+        let skus = draw(&candidates, &mut ChaCha20Rng::seed_from_u64(7)).unwrap();
+        let count = |sku: &str| skus.iter().filter(|drawn| *drawn == sku).count();
+        assert_eq!((count("SKU-0"), count("SKU-1"), count("SKU-2")), (2, 1, 2));
+        assert_eq!(
+            skus[..3]
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn draws_need_five_units() {
+        let candidates = candidates(&[2, 1, 1]);
+        assert_eq!(draw(&candidates, &mut ChaCha20Rng::seed_from_u64(7)), None);
+    }
+
+    #[test]
+    fn proofs_store_only_what_differs_from_the_catalogue() {
+        let catalogue = |rarities: &[usize]| {
+            let products = rarities
+                .iter()
+                .enumerate()
+                .map(|(i, rarity)| {
+                    format!("[[product]]\nname = \"Product {i}\"\nsku = \"SKU-{i}\"\npool = \"Red\"\nrarity = {rarity}\n")
+                })
+                .collect::<String>();
+            Products::from_toml(&products).unwrap()
+        };
+        let products = catalogue(&[1, 2, 3, 4]);
+        let mut candidates = candidates(&[5, 5, 2]);
+        candidates[1].weight = 8.0;
+        let proof = Proof::new(0, 0, &products, &candidates);
+        assert_eq!(
+            proof.adjusted,
+            BTreeMap::from([
+                ("SKU-1".to_owned(), (5, 8.0)),
+                ("SKU-2".to_owned(), (2, 3.0)),
+            ])
+        );
+        assert_eq!(proof.excluded, ["SKU-3"]);
+        assert_eq!(proof.candidates(&products), Some(candidates));
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof: Proof = serde_json::from_str(&json).unwrap();
+        assert!(proof.candidates(&products).is_some());
+        // Only changes to the products which could be drawn matter.
+        assert!(proof.candidates(&catalogue(&[1, 2, 3, 5])).is_some());
+        assert_eq!(proof.candidates(&catalogue(&[2, 2, 3, 4])), None);
+        assert_eq!(proof.candidates(&catalogue(&[1, 2, 3, 4, 5])), None);
+    }
+}
@@ -0,0 +1,19 @@
+use super::Products;
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+
+/// Daily free summons, which draw from their own catalogue of products. These products are not
+/// tracked in the shop's inventory, so may be digital-only rewards.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Daily {
+    /// The guilds which have opted in to daily summons.
+    pub guilds: Vec<GuildId>,
+    #[serde(default = "default_reset_hours")]
+    pub reset_hours: u32,
+    #[serde(flatten)]
+    pub products: Box<Products>,
+}
+
+fn default_reset_hours() -> u32 {
+    24
+}
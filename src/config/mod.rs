@@ -1,7 +1,17 @@
 mod banner;
+mod daily;
 mod pool;
+mod premium;
 mod products;
+mod set;
+mod shards;
+mod wishlist;
 
-pub use banner::Banner;
+pub use banner::{Banner, Candidate, Proof, draw};
+pub use daily::Daily;
 pub use pool::Pool;
-pub use products::{Product, Products};
+pub use premium::Premium;
+pub use products::{Product, Products, PullCounts};
+pub use set::Set;
+pub use shards::Shards;
+pub use wishlist::Wishlist;
@@ -9,6 +9,10 @@ pub enum Pool {
     White,
 }
 
+impl Pool {
+    pub const ALL: [Pool; 4] = [Pool::Red, Pool::Blue, Pool::Green, Pool::White];
+}
+
 impl Display for Pool {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -0,0 +1,25 @@
+use super::{Pool, Products};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Premium summons are drawn only from a restricted set of pools.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Premium {
+    pub pool: Vec<Pool>,
+}
+
+impl Premium {
+    /// Restricts allowances to the products in the premium pools.
+    pub fn restrict(
+        &self,
+        products: &Products,
+        mut allowances: HashMap<String, usize>,
+    ) -> HashMap<String, usize> {
+        allowances.retain(|sku, _| {
+            products
+                .iter()
+                .any(|product| product.sku == *sku && self.pool.contains(&product.pool))
+        });
+        allowances
+    }
+}
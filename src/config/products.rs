@@ -1,59 +1,260 @@
-use super::{Banner, Pool};
-use rand::distributions::Distribution as _;
-use rand::distributions::weighted::WeightedIndex;
+use super::{Banner, Candidate, Daily, Pool, Premium, Set, Shards, Wishlist};
+use crate::shopify::LineItem;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fmt::{self, Display};
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct Products {
+    #[serde(default)]
+    pub weighting: Weighting,
+    #[serde(default)]
     pub ticket: Vec<Ticket>,
     pub product: Vec<Product>,
+    #[serde(default)]
+    pub daily: Option<Daily>,
+    #[serde(default)]
+    pub shards: Option<Shards>,
+    #[serde(default)]
+    pub wishlist: Option<Wishlist>,
+    #[serde(default)]
+    pub set: Vec<Set>,
+    #[serde(default)]
+    pub premium: Option<Premium>,
+}
+
+/// How the chance of each product appearing in a banner is determined.
+#[derive(Clone, Copy, Default, Deserialize, Serialize, Debug)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Weighting {
+    /// Products are weighted by their rarity alone.
+    #[default]
+    Rarity,
+    /// Products are weighted by their rarity, scaled by their remaining stock raised to the
+    /// given exponent, so that products with more stock left are sold through faster.
+    Stock { exponent: f64 },
+}
+
+impl Weighting {
+    fn weight(&self, product: &Product, stock: usize) -> f64 {
+        match self {
+            Self::Rarity => product.rarity,
+            // Products which are not tracked in the inventory all have unlimited stock, so
+            // there is nothing to scale by.
+            Self::Stock { .. } if stock == usize::MAX => product.rarity,
+            Self::Stock { exponent } => product.rarity * (stock as f64).powf(*exponent),
+        }
+    }
 }
 
 impl Products {
     pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
-        toml::from_str(s)
+        let products: Self = toml::from_str(s)?;
+        products.validate().map_err(serde::de::Error::custom)?;
+        Ok(products)
+    }
+
+    fn validate(&self) -> Result<(), &'static str> {
+        if let Weighting::Stock { exponent } = self.weighting
+            && !(exponent.is_finite() && exponent >= 0.0)
+        {
+            return Err("weighting.exponent must be a number of at least 0");
+        }
+        if let Some(wishlist) = &self.wishlist
+            && !(wishlist.boost.is_finite() && wishlist.boost >= 1.0)
+        {
+            return Err("wishlist.boost must be a number of at least 1");
+        }
+        if let Some(daily) = &self.daily {
+            daily.products.validate()?;
+        }
+        Ok(())
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &Product> + '_ {
         self.product.iter()
     }
 
+    /// Finds a product by its SKU or name.
+    pub fn find(&self, query: &str) -> Option<&Product> {
+        self.product.iter().find(|product| {
+            product.sku.eq_ignore_ascii_case(query) || product.name.eq_ignore_ascii_case(query)
+        })
+    }
+
     pub fn distribution(&self) -> impl Display + '_ {
         Distribution(self)
     }
 
-    pub fn banner(&self, inventory: &HashMap<String, usize>) -> Banner {
-        let mut omit = HashSet::new();
-        let mut rng = thread_rng();
-
-        let products = std::array::from_fn(|_| {
-            let pool: Vec<_> = self
-                .product
-                .iter()
-                .filter(|item| inventory.get(&item.sku).copied().unwrap_or(0) > 0)
-                .filter(|item| !omit.contains(&item.sku))
-                .collect();
-            let weights = pool.iter().map(|product| product.rarity);
-            let weighted = WeightedIndex::new(weights).unwrap();
-            let index = weighted.sample(&mut rng);
-            let product = pool[index];
-            omit.insert(&product.sku);
-            product.clone()
-        });
-        Banner::new(products)
+    /// Stock for products which are not tracked in the shop's inventory, and so are unlimited.
+    pub fn unlimited_stock(&self) -> HashMap<String, usize> {
+        self.product
+            .iter()
+            .map(|product| (product.sku.clone(), usize::MAX))
+            .collect()
+    }
+
+    /// The number of distinct products which are still in stock.
+    pub fn products_in_stock(&self, inventory: &HashMap<String, usize>) -> usize {
+        self.product
+            .iter()
+            .filter(|product| stock(inventory, product) > 0)
+            .count()
+    }
+
+    /// The total number of units still in stock, across all products.
+    pub fn units_in_stock(&self, inventory: &HashMap<String, usize>) -> usize {
+        self.product
+            .iter()
+            .map(|product| stock(inventory, product))
+            .sum()
+    }
+
+    /// How many units of each product may still go into a banner, given the stock and each
+    /// product's limits. Products which may not be pulled at all are omitted.
+    pub fn allowances(
+        &self,
+        inventory: &HashMap<String, usize>,
+        pulled: &PullCounts,
+    ) -> HashMap<String, usize> {
+        self.product
+            .iter()
+            .map(|product| (product.sku.clone(), product.allowance(inventory, pulled)))
+            .filter(|(_, allowance)| *allowance > 0)
+            .collect()
+    }
+
+    /// The chance of each product in stock appearing in the first slot of a banner drawn from
+    /// the given pools, or from every pool. Later slots are drawn from the products not yet in
+    /// the banner, so have different chances. Wishlists, and limits on how many of a product a
+    /// user may pull, are not taken into account.
+    pub fn rates(
+        &self,
+        inventory: &HashMap<String, usize>,
+        pools: Option<&[Pool]>,
+    ) -> Vec<(&Product, f64)> {
+        let weights: Vec<_> = self
+            .product
+            .iter()
+            .filter(|product| pools.is_none_or(|pools| pools.contains(&product.pool)))
+            .map(|product| (product, stock(inventory, product)))
+            .filter(|(_, stock)| *stock > 0)
+            .map(|(product, stock)| (product, self.weighting.weight(product, stock)))
+            .collect();
+        let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+        weights
+            .into_iter()
+            .map(|(product, weight)| (product, weight / total))
+            .collect()
+    }
+
+    /// The products which may be drawn for a banner, in catalogue order. When weighting by
+    /// stock, products are weighted by their stock in the inventory, however many of them may
+    /// be pulled. Products on the wishlist are boosted.
+    pub fn candidates(
+        &self,
+        allowances: &HashMap<String, usize>,
+        inventory: &HashMap<String, usize>,
+        wishlist: &[String],
+    ) -> Vec<Candidate> {
+        let boost = self
+            .wishlist
+            .as_ref()
+            .map_or(1.0, |wishlist| wishlist.boost);
+        self.product
+            .iter()
+            .filter_map(|product| {
+                let allowance = allowances.get(&product.sku).copied().filter(|n| *n > 0)?;
+                // No more than fill the banner can be drawn, and capping the allowance there
+                // keeps proofs short.
+                let allowance = allowance.min(super::banner::SLOTS);
+                let weight = self.weighting.weight(product, stock(inventory, product));
+                Some(Candidate {
+                    sku: product.sku.clone(),
+                    allowance,
+                    weight: if wishlist.contains(&product.sku) {
+                        weight * boost
+                    } else {
+                        weight
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Generates a new banner from the given candidates. Products are not repeated within
+    /// a banner unless there are fewer than 5 distinct products left, in which case a product
+    /// may appear as many times as its allowance.
+    ///
+    /// Returns `None` if there are not enough units allowed to fill a banner.
+    pub fn banner(&self, candidates: &[Candidate], rng: &mut impl Rng) -> Option<Banner> {
+        let products: Vec<_> = super::draw(candidates, rng)?
+            .into_iter()
+            .map(|sku| {
+                self.product
+                    .iter()
+                    .find(|product| product.sku == sku)
+                    .cloned()
+            })
+            .collect::<Option<_>>()?;
+        Some(Banner::new(products.try_into().unwrap()))
     }
 }
 
+/// Summons granted for each unit of a matching line item on an order. A line item matches if it
+/// meets every criterion given.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct Ticket {
-    pub sku: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    /// The variant ID, either as a number or in full (`gid://shopify/ProductVariant/...`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
     #[serde(default)]
     pub bulks: usize,
     #[serde(default)]
     pub singles: usize,
+    #[serde(default)]
+    pub premiums: usize,
+    #[serde(default)]
+    pub rerolls: usize,
+}
+
+impl Ticket {
+    pub fn matches(&self, item: &LineItem) -> bool {
+        if self.sku.is_none()
+            && self.variant.is_none()
+            && self.tag.is_none()
+            && self.handle.is_none()
+        {
+            return false;
+        }
+        let sku = self
+            .sku
+            .as_ref()
+            .is_none_or(|sku| item.sku.as_ref() == Some(sku));
+        let variant = self.variant.as_ref().is_none_or(|id| {
+            item.variant.as_ref().is_some_and(|variant| {
+                variant.id == *id || variant.id.rsplit('/').next() == Some(id)
+            })
+        });
+        let tag = self.tag.as_ref().is_none_or(|tag| {
+            item.product
+                .as_ref()
+                .is_some_and(|product| product.tags.contains(tag))
+        });
+        let handle = self.handle.as_ref().is_none_or(|handle| {
+            item.product
+                .as_ref()
+                .is_some_and(|product| product.handle == *handle)
+        });
+        sku && variant && tag && handle
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -62,6 +263,55 @@ pub struct Product {
     pub sku: String,
     pub pool: Pool,
     pub rarity: f64,
+    /// The most of this product that may be pulled on a single order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_order: Option<usize>,
+    /// The most of this product that may be pulled by a single Discord user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_user: Option<usize>,
+    /// The most of this product that may be pulled by anyone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total: Option<usize>,
+}
+
+impl Product {
+    /// How many units of this product may still go into a banner, given the stock and
+    /// what has been pulled already.
+    fn allowance(&self, inventory: &HashMap<String, usize>, pulled: &PullCounts) -> usize {
+        [
+            (self.max_per_order, &pulled.order),
+            (self.max_per_user, &pulled.user),
+            (self.max_total, &pulled.total),
+        ]
+        .into_iter()
+        .filter_map(|(max, counts)| {
+            Some(max?.saturating_sub(counts.get(&self.sku).copied().unwrap_or(0)))
+        })
+        .fold(stock(inventory, self), usize::min)
+    }
+}
+
+/// The number of units of each SKU already pulled, against which product limits are enforced.
+#[derive(Default, Debug)]
+pub struct PullCounts {
+    pub order: HashMap<String, usize>,
+    pub user: HashMap<String, usize>,
+    pub total: HashMap<String, usize>,
+}
+
+impl PullCounts {
+    /// The counts of what has been pulled by anyone, leaving out the limits which apply only
+    /// to this order and its user.
+    pub fn shared(&self) -> Self {
+        Self {
+            total: self.total.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+fn stock(inventory: &HashMap<String, usize>, product: &Product) -> usize {
+    inventory.get(&product.sku).copied().unwrap_or(0)
 }
 
 struct Distribution<'a>(&'a Products);
@@ -78,6 +328,157 @@ impl Display for Distribution<'_> {
                 product.rarity, product.name, percentage
             )?;
         }
+        if let Weighting::Stock { exponent } = self.0.weighting {
+            writeln!(
+                f,
+                "Rates are scaled by remaining stock to the power of {exponent}"
+            )?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRODUCTS: &str = r#"
+        [weighting]
+        mode = "stock"
+        exponent = 1.0
+
+        [[product]]
+        name = "Limited"
+        sku = "LIMITED"
+        pool = "Red"
+        rarity = 1
+        max_per_order = 1
+
+        [[product]]
+        name = "Common"
+        sku = "COMMON"
+        pool = "Blue"
+        rarity = 1
+    "#;
+
+    #[test]
+    fn stock_weighting_uses_inventory_not_limits() {
+        let products = Products::from_toml(PRODUCTS).unwrap();
+        let inventory = HashMap::from([("LIMITED".to_owned(), 500), ("COMMON".to_owned(), 100)]);
+        let allowances = products.allowances(&inventory, &PullCounts::default());
+        let candidates = products.candidates(&allowances, &inventory, &[]);
+        assert_eq!(candidates[0].allowance, 1);
+        assert_eq!(candidates[0].weight, 500.0);
+        assert_eq!(candidates[1].weight, 100.0);
+    }
+
+    #[test]
+    fn allowances_are_capped_by_each_limit() {
+        let products = Products::from_toml(
+            r#"
+            [[product]]
+            name = "Limited"
+            sku = "LIMITED"
+            pool = "Red"
+            rarity = 1
+            max_per_order = 3
+            max_per_user = 4
+            max_total = 5
+            "#,
+        )
+        .unwrap();
+        let stock = |units| HashMap::from([("LIMITED".to_owned(), units)]);
+        let counts = |order, user, total| PullCounts {
+            order: HashMap::from([("LIMITED".to_owned(), order)]),
+            user: HashMap::from([("LIMITED".to_owned(), user)]),
+            total: HashMap::from([("LIMITED".to_owned(), total)]),
+        };
+        let allowance = |inventory, pulled| {
+            products
+                .allowances(&stock(inventory), &pulled)
+                .get("LIMITED")
+                .copied()
+        };
+        assert_eq!(allowance(10, PullCounts::default()), Some(3));
+        assert_eq!(allowance(10, counts(1, 1, 1)), Some(2));
+        assert_eq!(allowance(10, counts(0, 3, 3)), Some(1));
+        assert_eq!(allowance(10, counts(0, 0, 4)), Some(1));
+        assert_eq!(allowance(10, counts(3, 3, 3)), None);
+        assert_eq!(allowance(10, counts(0, 4, 4)), None);
+        assert_eq!(allowance(10, counts(0, 0, 5)), None);
+        assert_eq!(allowance(2, PullCounts::default()), Some(2));
+        assert_eq!(allowance(0, PullCounts::default()), None);
+    }
+
+    fn line_item() -> LineItem {
+        LineItem {
+            sku: Some("GACHA".to_owned()),
+            quantity: 1,
+            variant: Some(crate::shopify::Variant {
+                id: "gid://shopify/ProductVariant/123".to_owned(),
+            }),
+            product: Some(crate::shopify::Product {
+                handle: "kitty-emblem-gacha".to_owned(),
+                tags: vec!["gacha".to_owned(), "summon".to_owned()],
+            }),
+        }
+    }
+
+    fn ticket(criteria: &str) -> Ticket {
+        toml::from_str(&format!("{criteria}\nsingles = 1")).unwrap()
+    }
+
+    #[test]
+    fn tickets_match_variants_by_number_or_in_full() {
+        assert!(ticket(r#"variant = "123""#).matches(&line_item()));
+        assert!(ticket(r#"variant = "gid://shopify/ProductVariant/123""#).matches(&line_item()));
+        assert!(!ticket(r#"variant = "12""#).matches(&line_item()));
+        assert!(!ticket(r#"variant = "456""#).matches(&line_item()));
+    }
+
+    #[test]
+    fn tickets_match_tags_and_handles() {
+        assert!(ticket(r#"tag = "summon""#).matches(&line_item()));
+        assert!(!ticket(r#"tag = "pin""#).matches(&line_item()));
+        assert!(ticket(r#"handle = "kitty-emblem-gacha""#).matches(&line_item()));
+        assert!(!ticket(r#"handle = "kitty-emblem""#).matches(&line_item()));
+
+        let mut item = line_item();
+        item.product = None;
+        assert!(!ticket(r#"tag = "summon""#).matches(&item));
+        assert!(!ticket(r#"handle = "kitty-emblem-gacha""#).matches(&item));
+    }
+
+    #[test]
+    fn tickets_match_only_if_every_criterion_does() {
+        let both = ticket("sku = \"GACHA\"\ntag = \"summon\"");
+        assert!(both.matches(&line_item()));
+        let wrong_tag = ticket("sku = \"GACHA\"\ntag = \"pin\"");
+        assert!(!wrong_tag.matches(&line_item()));
+        let wrong_variant = ticket("handle = \"kitty-emblem-gacha\"\nvariant = \"456\"");
+        assert!(!wrong_variant.matches(&line_item()));
+    }
+
+    #[test]
+    fn tickets_without_criteria_match_nothing() {
+        assert!(!ticket("").matches(&line_item()));
+    }
+
+    #[test]
+    fn rejects_invalid_exponents() {
+        for exponent in ["-1.0", "nan", "inf"] {
+            let products = PRODUCTS.replace("exponent = 1.0", &format!("exponent = {exponent}"));
+            assert!(Products::from_toml(&products).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_boosts() {
+        for boost in ["0.5", "nan", "inf"] {
+            let products = format!("{PRODUCTS}\n[wishlist]\nsize = 3\nboost = {boost}\n");
+            assert!(Products::from_toml(&products).is_err());
+        }
+        let products = format!("{PRODUCTS}\n[wishlist]\nsize = 3\nboost = 2.0\n");
+        assert!(Products::from_toml(&products).is_ok());
+    }
+}
@@ -0,0 +1,19 @@
+use poise::serenity_prelude::RoleId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A set of products which, once a user has pulled every one of them across all their
+/// summons, earns them a role in the server.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Set {
+    pub name: String,
+    pub role: RoleId,
+    /// SKUs of the products in the set.
+    pub products: Vec<String>,
+}
+
+impl Set {
+    pub fn is_complete(&self, owned: &HashSet<String>) -> bool {
+        self.products.iter().all(|sku| owned.contains(sku))
+    }
+}
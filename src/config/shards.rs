@@ -0,0 +1,21 @@
+use super::{Pool, Product};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Shards are given when a user pulls a product they already own, and can be spent on extra
+/// single summons.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Shards {
+    /// The number of shards it takes to buy a single summon.
+    pub cost: usize,
+    /// The number of shards given for a duplicate from each pool.
+    #[serde(default)]
+    pub pool: HashMap<Pool, usize>,
+}
+
+impl Shards {
+    /// The number of shards given for a duplicate of a product.
+    pub fn value(&self, product: &Product) -> usize {
+        self.pool.get(&product.pool).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Users may wishlist products, which makes those products more likely to appear in their
+/// banners.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Wishlist {
+    /// The most products a user may have on their wishlist.
+    pub size: usize,
+    /// The factor by which a wishlisted product's weight is multiplied.
+    pub boost: f64,
+}
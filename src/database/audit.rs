@@ -0,0 +1,51 @@
+use poise::serenity_prelude::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// A change made to an order's summons other than by summoning, kept so that staff can see
+/// what happened to it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditEntry {
+    pub at: Timestamp,
+    /// The username of whoever made the change.
+    pub by: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    /// Staff added (or removed, if negative) summons.
+    Grant {
+        singles: i64,
+        bulks: i64,
+        reason: String,
+    },
+    /// Staff cleared a stuck active banner.
+    Reset { reason: String },
+    /// Staff undid a pull, returning the product to stock.
+    Void { sku: String, reason: String },
+    /// A product was traded for one of another user's, which now ship with each other's
+    /// orders.
+    Trade {
+        gave: String,
+        received: String,
+        /// The Discord user ID of the other party.
+        with: String,
+        /// The record the other party's product came from.
+        record: String,
+    },
+    /// Unused summons were given from one user's record to another's.
+    Gift {
+        from: String,
+        to: String,
+        singles: usize,
+        bulks: usize,
+    },
+    /// The summons were reassigned to another user.
+    Transfer {
+        from: String,
+        to: String,
+        reason: String,
+    },
+}
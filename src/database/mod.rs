@@ -1,17 +1,28 @@
 use a1_notation::Address;
+use poise::serenity_prelude::User;
 use sheets::types::{
-    DateTimeRenderOption, Dimension, InsertDataOption, ValueInputOption, ValueRange,
-    ValueRenderOption,
+    BatchUpdateValuesRequest, DateTimeRenderOption, Dimension, InsertDataOption, ValueInputOption,
+    ValueRange, ValueRenderOption,
 };
 use std::collections::HashMap;
 use std::io::Write;
 
+mod audit;
+mod profile;
+mod promo;
 mod pulls_data;
+mod record_id;
 mod row;
+mod seed;
 
-use crate::shopify::OrderNumber;
-pub use pulls_data::PullsData;
+use crate::config::PullCounts;
+pub use audit::{AuditEntry, Change};
+pub use profile::Profile;
+pub use promo::Promo;
+pub use pulls_data::{BannerKind, PullsData, Wallet};
+pub use record_id::RecordId;
 pub use row::Row;
+pub use seed::Seed;
 
 #[derive(Clone)]
 pub struct Sheets {
@@ -67,62 +78,42 @@ impl Sheets {
         Ok(())
     }
 
-    pub async fn database(&self) -> Result<HashMap<OrderNumber, Row>, sheets::ClientError> {
+    async fn refresh(&self) -> Result<(), sheets::ClientError> {
         if self.client.is_expired().await != Some(false) {
             log::debug!("Refreshing access token");
             self.client.refresh_access_token().await?;
         }
-        let spreadsheet = self
-            .client
-            .spreadsheets()
-            .get(&self.sheet_id, false, &[])
-            .await?;
-        log::debug!("Sheet retrieved");
-        let properties = spreadsheet.body.sheets[0].properties.as_ref().unwrap();
-        let grid_properties = properties.grid_properties.as_ref().unwrap();
-        let response = self
+        Ok(())
+    }
+
+    async fn read(&self, range: &str) -> Result<Vec<Vec<String>>, sheets::ClientError> {
+        self.refresh().await?;
+        Ok(self
             .client
             .spreadsheets()
             .values_get(
                 &self.sheet_id,
-                &format!("A1:{}", Address::new(3, grid_properties.row_count as usize)),
+                range,
                 DateTimeRenderOption::Noop,
                 Dimension::Rows,
                 ValueRenderOption::Noop,
             )
             .await?
-            .body;
-        let rows = response
-            .values
-            .into_iter()
-            .enumerate()
-            .filter_map(|(i, row)| {
-                let mut row = Row::try_from(row).ok()?;
-                row.existing = Some(i);
-                Some(row)
-            })
-            .collect::<Vec<Row>>();
-        log::debug!("{} rows loaded", rows.len());
-
-        Ok(rows
-            .into_iter()
-            .map(|row| (row.order_number, row))
-            .collect())
+            .body
+            .values)
     }
 
-    pub async fn get_order(
+    /// Writes a row of values to a sheet, defaulting to the first sheet. Rows which do not
+    /// already exist are appended.
+    async fn write(
         &self,
-        order_number: OrderNumber,
-    ) -> Result<Option<Row>, sheets::ClientError> {
-        let mut database = self.database().await?;
-        Ok(database.remove(&order_number))
-    }
-
-    pub async fn save(&self, row: Row) -> Result<(), sheets::ClientError> {
-        let row_index = row.existing;
-        let values = row.into_cells();
+        sheet: Option<&str>,
+        row_index: Option<usize>,
+        values: Vec<String>,
+    ) -> Result<(), sheets::ClientError> {
         let range = format!(
-            "{}:{}",
+            "{}{}:{}",
+            sheet.map(|sheet| format!("'{sheet}'!")).unwrap_or_default(),
             Address::new(0, row_index.unwrap_or(0)),
             Address::new(values.len() - 1, row_index.unwrap_or(0))
         );
@@ -164,4 +155,253 @@ impl Sheets {
         }
         Ok(())
     }
+
+    pub async fn database(&self) -> Result<HashMap<RecordId, Row>, sheets::ClientError> {
+        self.refresh().await?;
+        let spreadsheet = self
+            .client
+            .spreadsheets()
+            .get(&self.sheet_id, false, &[])
+            .await?;
+        log::debug!("Sheet retrieved");
+        let properties = spreadsheet.body.sheets[0].properties.as_ref().unwrap();
+        let grid_properties = properties.grid_properties.as_ref().unwrap();
+        let rows = self
+            .read(&format!(
+                "A1:{}",
+                Address::new(3, grid_properties.row_count as usize)
+            ))
+            .await?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, row)| {
+                let mut row = Row::try_from(row).ok()?;
+                row.existing = Some(i);
+                Some(row)
+            })
+            .collect::<Vec<Row>>();
+        log::debug!("{} rows loaded", rows.len());
+
+        Ok(rows.into_iter().map(|row| (row.id, row)).collect())
+    }
+
+    pub async fn get_record(
+        &self,
+        id: impl Into<RecordId>,
+    ) -> Result<Option<Row>, sheets::ClientError> {
+        let mut database = self.database().await?;
+        Ok(database.remove(&id.into()))
+    }
+
+    pub async fn save(&self, row: Row) -> Result<(), sheets::ClientError> {
+        let row_index = row.existing;
+        self.write(None, row_index, row.into_cells()).await
+    }
+
+    /// Saves several rows which already exist in a single request, so that either all of them
+    /// are written or none are.
+    pub async fn save_all(&self, rows: Vec<Row>) -> Result<(), sheets::ClientError> {
+        self.refresh().await?;
+        let data = rows
+            .into_iter()
+            .map(|row| {
+                let row_index = row
+                    .existing
+                    .expect("only existing rows can be saved together");
+                let values = row.into_cells();
+                let range = format!(
+                    "{}:{}",
+                    Address::new(0, row_index),
+                    Address::new(values.len() - 1, row_index)
+                );
+                ValueRange {
+                    major_dimension: Some(Dimension::Rows),
+                    range,
+                    values: vec![values],
+                }
+            })
+            .collect();
+        self.client
+            .spreadsheets()
+            .values_batch_update(
+                &self.sheet_id,
+                &BatchUpdateValuesRequest {
+                    data,
+                    include_values_in_response: None,
+                    response_date_time_render_option: None,
+                    response_value_render_option: None,
+                    value_input_option: Some(ValueInputOption::Raw),
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_promo(&self, code: &str) -> Result<Option<Promo>, sheets::ClientError> {
+        let code = code.to_uppercase();
+        Ok(self
+            .read(&format!("'{}'!A:H", promo::SHEET))
+            .await?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, row)| {
+                let mut promo = Promo::try_from(row).ok()?;
+                promo.existing = Some(i);
+                Some(promo)
+            })
+            .find(|promo| promo.code == code))
+    }
+
+    /// Whether the spreadsheet has a sheet with the given name.
+    async fn has_sheet(&self, name: &str) -> Result<bool, sheets::ClientError> {
+        self.refresh().await?;
+        let spreadsheet = self
+            .client
+            .spreadsheets()
+            .get(&self.sheet_id, false, &[])
+            .await?;
+        Ok(spreadsheet
+            .body
+            .sheets
+            .iter()
+            .filter_map(|sheet| sheet.properties.as_ref())
+            .any(|properties| properties.title == name))
+    }
+
+    /// Retrieves a user's profile, or a new one if they do not have one yet. Profiles are only
+    /// needed by optional features, so if there is no sheet for them, every profile is new.
+    pub async fn get_profile(&self, user: &User) -> Result<Profile, sheets::ClientError> {
+        let discord_user_id = user.id.to_string();
+        let rows = match self.read(&format!("'{}'!A:C", profile::SHEET)).await {
+            Ok(rows) => rows,
+            Err(error) => {
+                if self.has_sheet(profile::SHEET).await? {
+                    return Err(error);
+                }
+                vec![]
+            }
+        };
+        let profile = rows
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, row)| {
+                let mut profile = Profile::try_from(row).ok()?;
+                profile.existing = Some(i);
+                Some(profile)
+            })
+            .find(|profile| profile.discord_user_id == discord_user_id);
+        Ok(profile.unwrap_or_else(|| Profile::new(discord_user_id, user.name.to_owned())))
+    }
+
+    pub async fn save_profile(&self, profile: Profile) -> Result<(), sheets::ClientError> {
+        let row_index = profile.existing;
+        self.write(Some(profile::SHEET), row_index, profile.into_cells())
+            .await
+    }
+
+    pub async fn save_promo(&self, promo: Promo) -> Result<(), sheets::ClientError> {
+        let row_index = promo.existing;
+        self.write(Some(promo::SHEET), row_index, promo.into_cells())
+            .await
+    }
+}
+
+/// Counts the units of each SKU which are held in reserve by active banners.
+pub fn reserved<'a>(rows: impl IntoIterator<Item = &'a Row>) -> HashMap<String, usize> {
+    let mut reserved = HashMap::new();
+    for sku in rows.into_iter().flat_map(|row| row.pulls.reserved_skus()) {
+        *reserved.entry(sku.to_owned()).or_default() += 1;
+    }
+    reserved
+}
+
+/// Counts the units of each SKU which have been pulled, or are reserved by active banners, for
+/// the given row's order, for its user, and in total.
+pub fn pull_counts<'a>(row: &Row, others: impl IntoIterator<Item = &'a Row>) -> PullCounts {
+    let mut counts = PullCounts::default();
+    for sku in row.pulls.skus() {
+        *counts.order.entry(sku.clone()).or_default() += 1;
+        *counts.user.entry(sku.clone()).or_default() += 1;
+        *counts.total.entry(sku).or_default() += 1;
+    }
+    for other in others {
+        let skus = other
+            .pulls
+            .skus()
+            .chain(other.pulls.reserved_skus().map(ToOwned::to_owned));
+        for sku in skus {
+            if other.discord_user_id == row.discord_user_id {
+                *counts.user.entry(sku.clone()).or_default() += 1;
+            }
+            *counts.total.entry(sku).or_default() += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Products;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn products() -> Products {
+        let products = (0..5)
+            .map(|i| {
+                format!("[[product]]\nname = \"Product {i}\"\nsku = \"SKU-{i}\"\npool = \"Red\"\nrarity = 1\n")
+            })
+            .collect::<String>();
+        Products::from_toml(&products).unwrap()
+    }
+
+    /// A row for a full summon of every product, which has been summoned if `pulled`, and
+    /// otherwise holds the products in reserve.
+    fn row(order_number: &str, user: &str, pulled: bool) -> Row {
+        let products = products();
+        let id: RecordId = order_number.parse().unwrap();
+        let mut pulls = PullsData::new(0, 1);
+        pulls
+            .start_banner_bulk(
+                id,
+                &products,
+                &products.unlimited_stock(),
+                &PullCounts::default(),
+                &[],
+                &mut ChaCha20Rng::seed_from_u64(7),
+            )
+            .unwrap();
+        if pulled {
+            for slot in 0..5 {
+                pulls.pull_slot(slot).unwrap();
+            }
+        }
+        Row::new(id, user.to_owned(), user.to_owned(), pulls)
+    }
+
+    #[test]
+    fn pull_counts_include_other_orders_and_reserved_stock() {
+        let current = row("1", "A", true);
+        let others = [
+            row("2", "A", true),
+            row("3", "A", false),
+            row("4", "B", true),
+            row("5", "B", false),
+        ];
+        let counts = pull_counts(&current, &others);
+        for i in 0..5 {
+            let sku = format!("SKU-{i}");
+            assert_eq!(counts.order[&sku], 1);
+            assert_eq!(counts.user[&sku], 3);
+            assert_eq!(counts.total[&sku], 5);
+        }
+    }
+
+    #[test]
+    fn reserved_counts_only_active_banners() {
+        let rows = [row("1", "A", true), row("2", "A", false)];
+        let reserved = reserved(&rows);
+        assert_eq!(reserved.len(), 5);
+        assert!(reserved.values().all(|count| *count == 1));
+    }
 }
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// The sheet in which user profiles are stored.
+pub(super) const SHEET: &str = "Users";
+
+/// Data kept about a Discord user, across all of their summons.
+#[derive(Debug)]
+pub struct Profile {
+    pub discord_user_id: String,
+    pub discord_username: String,
+    pub data: ProfileData,
+
+    pub(super) existing: Option<usize>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub struct ProfileData {
+    /// Shards earned from duplicate pulls, which can be spent on extra summons.
+    #[serde(default)]
+    pub shards: usize,
+    /// SKUs of the products the user is hoping to summon.
+    #[serde(default)]
+    pub wishlist: Vec<String>,
+    /// Tokens which can be spent to discard a fresh banner and generate a new one.
+    #[serde(default)]
+    pub rerolls: usize,
+}
+
+impl Profile {
+    pub fn new(discord_user_id: String, discord_username: String) -> Self {
+        Self {
+            discord_user_id,
+            discord_username,
+            data: ProfileData::default(),
+            existing: None,
+        }
+    }
+
+    pub(super) fn into_cells(self) -> Vec<String> {
+        vec![
+            self.discord_user_id,
+            self.discord_username,
+            serde_json::to_string(&self.data).unwrap(),
+        ]
+    }
+}
+
+impl TryFrom<Vec<String>> for Profile {
+    type Error = ();
+
+    fn try_from(row: Vec<String>) -> Result<Self, Self::Error> {
+        let mut row = row.into_iter();
+        Ok(Self {
+            discord_user_id: row.next().ok_or(())?,
+            discord_username: row.next().ok_or(())?,
+            data: serde_json::from_str(&row.next().ok_or(())?).map_err(|_| ())?,
+
+            existing: None,
+        })
+    }
+}
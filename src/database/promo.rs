@@ -0,0 +1,110 @@
+use crate::CustomError;
+use poise::serenity_prelude::{Timestamp, UserId};
+
+/// The sheet in which promo codes are stored.
+pub(super) const SHEET: &str = "Promos";
+
+#[derive(Debug)]
+pub struct Promo {
+    pub code: String,
+    pub singles: usize,
+    pub bulks: usize,
+    pub rerolls: usize,
+    pub max_uses: usize,
+    pub expires_at: Option<Timestamp>,
+    pub created_by: String,
+    pub redeemed_by: Vec<UserId>,
+
+    pub(super) existing: Option<usize>,
+}
+
+impl Promo {
+    pub fn new(
+        code: String,
+        singles: usize,
+        bulks: usize,
+        rerolls: usize,
+        max_uses: usize,
+        expires_at: Option<Timestamp>,
+        created_by: String,
+    ) -> Self {
+        Self {
+            code: code.to_uppercase(),
+            singles,
+            bulks,
+            rerolls,
+            max_uses,
+            expires_at,
+            created_by,
+            redeemed_by: vec![],
+            existing: None,
+        }
+    }
+
+    pub fn redeem(&mut self, user_id: UserId) -> Result<(), CustomError> {
+        if self
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Timestamp::now())
+        {
+            return Err(CustomError("This promo code has expired.".to_owned()));
+        }
+        if self.redeemed_by.contains(&user_id) {
+            return Err(CustomError(
+                "You have already redeemed this promo code.".to_owned(),
+            ));
+        }
+        if self.redeemed_by.len() >= self.max_uses {
+            return Err(CustomError(
+                "This promo code has already been fully redeemed.".to_owned(),
+            ));
+        }
+        self.redeemed_by.push(user_id);
+        Ok(())
+    }
+
+    pub(super) fn into_cells(self) -> Vec<String> {
+        vec![
+            self.code,
+            self.singles.to_string(),
+            self.bulks.to_string(),
+            self.max_uses.to_string(),
+            self.expires_at
+                .map(|expires_at| expires_at.to_string())
+                .unwrap_or_default(),
+            self.created_by,
+            serde_json::to_string(&self.redeemed_by).unwrap(),
+            self.rerolls.to_string(),
+        ]
+    }
+}
+
+impl TryFrom<Vec<String>> for Promo {
+    type Error = ();
+
+    fn try_from(row: Vec<String>) -> Result<Self, Self::Error> {
+        let mut row = row.into_iter();
+        Ok(Self {
+            code: row.next().ok_or(())?,
+            singles: row.next().ok_or(())?.parse().map_err(|_| ())?,
+            bulks: row.next().ok_or(())?.parse().map_err(|_| ())?,
+            max_uses: row.next().ok_or(())?.parse().map_err(|_| ())?,
+            expires_at: Some(row.next().ok_or(())?)
+                .filter(|expires_at| !expires_at.is_empty())
+                .map(|expires_at| expires_at.parse().map_err(|_| ()))
+                .transpose()?,
+            created_by: row.next().ok_or(())?,
+            redeemed_by: row
+                .next()
+                .map(|redeemed_by| serde_json::from_str(&redeemed_by).map_err(|_| ()))
+                .transpose()?
+                .unwrap_or_default(),
+            rerolls: row
+                .next()
+                .map(|rerolls| rerolls.parse().map_err(|_| ()))
+                .transpose()?
+                .unwrap_or_default(),
+
+            existing: None,
+        })
+    }
+}
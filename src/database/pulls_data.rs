@@ -1,12 +1,18 @@
-use crate::config::{Banner, Pool, Product, Products};
-use crate::shopify::OrderNumber;
+use super::{AuditEntry, Change, RecordId, Seed};
+use crate::config::{Banner, Pool, Premium, Product, Products, Proof, PullCounts, draw};
 use crate::{Action, CustomError, InteractionType};
 use poise::CreateReply;
 use poise::serenity_prelude::*;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::collections::HashMap;
 use std::fmt::Write;
 
+/// How long an unfinished single summon holds its products in reserve before it is
+/// considered abandoned.
+const RESERVATION_TIMEOUT_SECS: i64 = 30 * 60;
+
 pub struct Message {
     message: String,
     buttons: Vec<CreateActionRow>,
@@ -14,20 +20,16 @@ pub struct Message {
 }
 
 impl Message {
-    pub fn into_interaction_response(
-        self,
-    ) -> (CreateInteractionResponseMessage, Vec<CreateAttachment>) {
-        let response = CreateInteractionResponseMessage::new()
+    /// The edit which replaces an interaction's message with this one.
+    pub fn into_edit_response(self) -> EditInteractionResponse {
+        let response = EditInteractionResponse::new()
             .content(self.message)
-            .components(self.buttons)
-            .ephemeral(true);
+            .components(self.buttons);
 
         if let Some(image) = self.image {
-            let file_placeholder = CreateAttachment::bytes(vec![], "summon.png");
-            let file = CreateAttachment::bytes(image, "summon.png");
-            (response.add_file(file_placeholder), vec![file])
+            response.new_attachment(CreateAttachment::bytes(image, "summon.png"))
         } else {
-            (response, vec![])
+            response.clear_attachments()
         }
     }
 
@@ -45,17 +47,43 @@ impl Message {
     }
 }
 
+/// A user's shards, shown alongside their summons.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Wallet {
+    pub shards: usize,
+    /// The number of shards it takes to buy a single summon, if shards are enabled.
+    pub cost: Option<usize>,
+    pub rerolls: usize,
+}
+
+/// The kinds of banner, for staff to refer to them by.
+#[derive(Clone, Copy, Debug, poise::ChoiceParameter)]
+pub enum BannerKind {
+    Single,
+    #[name = "Full"]
+    Bulk,
+    Premium,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ActiveBanner {
     Single(Banner),
     Bulk(Banner),
+    Premium(Banner),
     None,
 }
 
 impl ActiveBanner {
     fn as_banner(&self) -> Option<&Banner> {
         match &self {
-            Self::Single(banner) | Self::Bulk(banner) => Some(banner),
+            Self::Single(banner) | Self::Bulk(banner) | Self::Premium(banner) => Some(banner),
+            _ => None,
+        }
+    }
+
+    fn as_banner_mut(&mut self) -> Option<&mut Banner> {
+        match self {
+            Self::Single(banner) | Self::Bulk(banner) | Self::Premium(banner) => Some(banner),
             _ => None,
         }
     }
@@ -67,7 +95,32 @@ pub struct PullsData {
     pub singles: usize,
     pub bulk_pulls: Vec<Banner>,
     pub single_pulls: Vec<Banner>,
+    /// Premium summons, which draw a single product from the premium pools.
+    #[serde(default)]
+    pub premiums: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub premium_pulls: Vec<Banner>,
     pub active: ActiveBanner,
+    /// Fresh banners which were discarded, using reroll tokens or by staff, kept for auditing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rerolled: Vec<Banner>,
+    /// Server seeds for this order, of which only the last may be unrevealed.
+    #[serde(default)]
+    pub seeds: Vec<Seed>,
+    /// The number of banners generated for this order so far.
+    #[serde(default)]
+    nonce: usize,
+    /// When daily summons were last claimed, in each guild.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub claimed: HashMap<GuildId, Timestamp>,
+    /// Products received from other users in trades, which now ship with this order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traded_in: Vec<Product>,
+    /// SKUs of products given to other users in trades, which no longer ship with this order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traded_out: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audit: Vec<AuditEntry>,
 }
 
 impl PullsData {
@@ -77,27 +130,198 @@ impl PullsData {
             bulks,
             bulk_pulls: vec![],
             single_pulls: vec![],
+            premiums: 0,
+            premium_pulls: vec![],
             active: ActiveBanner::None,
+            rerolled: vec![],
+            seeds: vec![],
+            nonce: 0,
+            claimed: HashMap::new(),
+            traded_in: vec![],
+            traded_out: vec![],
+            audit: vec![],
         }
     }
 
-    #[allow(dead_code)]
-    pub fn skus(&self) -> impl Iterator<Item = String> + '_ {
+    /// Records a change made to these summons.
+    pub fn record(&mut self, by: String, change: Change) {
+        self.audit.push(AuditEntry {
+            at: Timestamp::now(),
+            by,
+            change,
+        });
+    }
+
+    /// Adds summons, or removes them if negative. Summons which have already been used may
+    /// not be removed.
+    pub fn grant(&mut self, singles: i64, bulks: i64) -> Result<(), CustomError> {
+        let adjust = |count: usize, by: i64, used: usize, kind: &str| {
+            count
+                .checked_add_signed(by as isize)
+                .filter(|count| *count >= used)
+                .ok_or_else(|| {
+                    CustomError(format!(
+                        "Cannot remove {} {kind} summons, as only {} are unused.",
+                        -by,
+                        count - used
+                    ))
+                })
+        };
+        let singles = adjust(self.singles, singles, self.pulled_singles(), "single")?;
+        let bulks = adjust(self.bulks, bulks, self.pulled_bulks(), "full")?;
+        self.singles = singles;
+        self.bulks = bulks;
+        Ok(())
+    }
+
+    /// Claims a daily summon in a guild, if it has been long enough since the last one.
+    pub fn claim_daily(&mut self, guild_id: GuildId, reset_hours: u32) -> Result<(), CustomError> {
+        let now = Timestamp::now();
+        if let Some(claimed) = self.claimed.get(&guild_id) {
+            let reset = claimed.unix_timestamp() + i64::from(reset_hours) * 60 * 60;
+            if reset > now.unix_timestamp() {
+                return Err(CustomError(format!(
+                    "You have already claimed your daily summon. The next one is available <t:{reset}:R>."
+                )));
+            }
+        }
+        self.claimed.insert(guild_id, now);
+        self.singles += 1;
+        Ok(())
+    }
+
+    /// The server seed that upcoming banners will be generated from, committing to a new one
+    /// if there is not already one unrevealed.
+    pub fn commit_seed(&mut self, rng: &mut impl Rng) -> &Seed {
+        if self.seeds.last().is_none_or(|seed| seed.revealed) {
+            self.seeds.push(Seed::generate(rng));
+        }
+        self.seeds.last().unwrap()
+    }
+
+    fn banners(&self) -> impl Iterator<Item = &Banner> + '_ {
         self.bulk_pulls
             .iter()
             .chain(self.single_pulls.iter())
+            .chain(self.premium_pulls.iter())
+            .chain(self.rerolled.iter())
             .chain(self.active.as_banner())
+    }
+
+    /// Every product summoned on this order.
+    pub fn pulled_products(&self) -> impl Iterator<Item = &Product> + '_ {
+        self.banners().flat_map(|banner| banner.pulled_products())
+    }
+
+    /// Every product summoned on this order in the given server.
+    pub fn pulled_products_in(&self, guild_id: GuildId) -> impl Iterator<Item = &Product> + '_ {
+        self.banners()
+            .filter(move |banner| banner.guild_id == Some(guild_id))
             .flat_map(|banner| banner.pulled_products())
-            .map(|product| product.sku.to_owned())
     }
 
-    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
-        self.bulk_pulls
+    /// Records the server the active banner is being summoned in, unless it already belongs to
+    /// one. Returns the server it belongs to.
+    pub fn set_guild(&mut self, guild_id: Option<GuildId>) -> Option<GuildId> {
+        let banner = self.active.as_banner_mut()?;
+        if banner.guild_id.is_none() {
+            banner.guild_id = guild_id;
+        }
+        banner.guild_id
+    }
+
+    /// The products which ship with this order: those summoned, along with any traded for,
+    /// less any traded away.
+    pub fn owned_products(&self) -> impl Iterator<Item = &Product> + '_ {
+        let mut given = HashMap::<&str, usize>::new();
+        for sku in &self.traded_out {
+            *given.entry(sku).or_default() += 1;
+        }
+        self.pulled_products()
+            .chain(&self.traded_in)
+            .filter(move |product| match given.get_mut(product.sku.as_str()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            })
+    }
+
+    /// An upper bound on the number of names which have ever been written for this order, as
+    /// trades and voids may have since removed some.
+    pub fn names_written(&self) -> usize {
+        let voided = self
+            .audit
             .iter()
-            .chain(self.single_pulls.iter())
-            .chain(self.active.as_banner())
-            .flat_map(|banner| banner.pulled_products())
-            .map(|product| product.name.to_owned())
+            .filter(|entry| matches!(entry.change, Change::Void { .. }))
+            .count();
+        self.pulled_products().count() + self.traded_in.len() + voided
+    }
+
+    /// Exchanges an owned product for another user's.
+    pub fn trade(&mut self, gave: &str, received: Product) -> Result<(), CustomError> {
+        if !self.owned_products().any(|product| product.sku == gave) {
+            return Err(CustomError(
+                "A product in this trade is no longer owned.".to_owned(),
+            ));
+        }
+        self.traded_out.push(gave.to_owned());
+        self.traded_in.push(received);
+        Ok(())
+    }
+
+    /// SKUs of every product summoned on this order, which count towards product limits even
+    /// once traded away.
+    pub fn skus(&self) -> impl Iterator<Item = String> + '_ {
+        self.pulled_products().map(|product| product.sku.to_owned())
+    }
+
+    /// SKUs of the products which ship with this order.
+    pub fn owned_skus(&self) -> impl Iterator<Item = String> + '_ {
+        self.owned_products().map(|product| product.sku.to_owned())
+    }
+
+    /// Whether the active banner is a single summon which has gone untouched for too long, and
+    /// so no longer holds its products in reserve.
+    pub fn is_abandoned(&self) -> bool {
+        match &self.active {
+            ActiveBanner::Single(banner) => banner.updated_at.is_none_or(|updated_at| {
+                Timestamp::now().unix_timestamp() - updated_at.unix_timestamp()
+                    >= RESERVATION_TIMEOUT_SECS
+            }),
+            _ => false,
+        }
+    }
+
+    /// Whether the unrevealed products of the active banner are being held for this order.
+    ///
+    /// A full or premium summon has already been paid for, so it is held until it is complete.
+    /// A single summon is only held while there are singles left to continue it with, and until
+    /// it is abandoned.
+    fn is_reserved(&self) -> bool {
+        match &self.active {
+            ActiveBanner::Bulk(..) => true,
+            ActiveBanner::Premium(banner) => banner.pulled() == 0,
+            ActiveBanner::Single(..) => {
+                self.singles > self.pulled_singles() && !self.is_abandoned()
+            }
+            ActiveBanner::None => false,
+        }
+    }
+
+    /// SKUs which are held for this order by the active banner, but have not yet been revealed.
+    pub fn reserved_skus(&self) -> impl Iterator<Item = &str> + '_ {
+        self.active
+            .as_banner()
+            .filter(|_| self.is_reserved())
+            .into_iter()
+            .flat_map(|banner| banner.unrevealed_products())
+            .map(|product| product.sku.as_str())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+        self.owned_products().map(|product| product.name.to_owned())
     }
 
     fn pulled_singles(&self) -> usize {
@@ -118,18 +342,37 @@ impl PullsData {
         }
     }
 
+    fn pulled_premiums(&self) -> usize {
+        let past = self.premium_pulls.len();
+        if matches!(self.active, ActiveBanner::Premium(..)) {
+            past + 1
+        } else {
+            past
+        }
+    }
+
+    /// The full, single and premium summons which have not been used yet.
+    pub fn remaining(&self) -> (usize, usize, usize) {
+        (
+            self.bulks - self.pulled_bulks(),
+            self.singles - self.pulled_singles(),
+            self.premiums - self.pulled_premiums(),
+        )
+    }
+
     pub fn to_message(
         &self,
-        order_number: OrderNumber,
+        id: RecordId,
+        wallet: Wallet,
         extra: Option<String>,
     ) -> Result<Message, crate::Error> {
-        let singles_available = self.singles - self.pulled_singles();
-        let bulks_available = self.bulks - self.pulled_bulks();
+        let (bulks_available, singles_available, premiums_available) = self.remaining();
 
         let start_banner = match &self.active {
             ActiveBanner::None => true,
             ActiveBanner::Single(banner) if banner.pulled() > 0 => true,
             ActiveBanner::Bulk(banner) if banner.pulled() == 5 => true,
+            ActiveBanner::Premium(banner) if banner.pulled() > 0 => true,
             _ => false,
         };
 
@@ -140,6 +383,7 @@ impl PullsData {
                 Some(banner)
             }
             ActiveBanner::Bulk(banner) if banner.pulled() != 5 => Some(banner),
+            ActiveBanner::Premium(banner) if banner.pulled() == 0 => Some(banner),
             _ => None,
         };
         if let Some(banner) = continue_banner {
@@ -149,31 +393,67 @@ impl PullsData {
                     row.push(
                         CreateButton::new(
                             serde_json::to_string(&InteractionType {
-                                order_number,
+                                id,
                                 action: Action::Pull(i),
                             })
                             .unwrap(),
                         )
                         .label(format!("Summon {} #{}", slot.pool, i + 1))
-                        .style(match slot.pool {
-                            Pool::Red => ButtonStyle::Danger,
-                            Pool::Green => ButtonStyle::Success,
-                            Pool::Blue => ButtonStyle::Primary,
-                            Pool::White => ButtonStyle::Secondary,
-                        }),
+                        .style(pool_style(slot.pool)),
                     );
                 }
             }
             buttons.push(CreateActionRow::Buttons(row));
         }
 
+        if let Some(banner) =
+            continue_banner.filter(|_| matches!(self.active, ActiveBanner::Bulk(..)))
+        {
+            let mut pools = vec![];
+            for slot in self.remaining_slots(None) {
+                let pool = banner.slots[slot].pool;
+                if !pools.contains(&pool) {
+                    pools.push(pool);
+                }
+            }
+            if pools.len() > 1 {
+                let row = pools
+                    .into_iter()
+                    .map(|pool| {
+                        CreateButton::new(
+                            serde_json::to_string(&InteractionType {
+                                id,
+                                action: Action::PullPool(pool),
+                            })
+                            .unwrap(),
+                        )
+                        .label(format!("Summon all {pool} orbs"))
+                        .style(pool_style(pool))
+                    })
+                    .collect();
+                buttons.push(CreateActionRow::Buttons(row));
+            }
+        }
+
         let mut row = vec![];
+        if continue_banner.is_some() && matches!(self.active, ActiveBanner::Bulk(..)) {
+            row.push(
+                CreateButton::new(
+                    serde_json::to_string(&InteractionType {
+                        id,
+                        action: Action::PullAll,
+                    })
+                    .unwrap(),
+                )
+                .label("Summon all remaining"),
+            );
+        }
         if start_banner {
             if singles_available > 0 {
                 row.push(
                     CreateButton::new(
                         serde_json::to_string(&InteractionType {
-                            order_number,
+                            id,
                             action: Action::Single,
                         })
                         .unwrap(),
@@ -186,7 +466,7 @@ impl PullsData {
                 row.push(
                     CreateButton::new(
                         serde_json::to_string(&InteractionType {
-                            order_number,
+                            id,
                             action: Action::Bulk,
                         })
                         .unwrap(),
@@ -194,12 +474,26 @@ impl PullsData {
                     .label("Start new full summon"),
                 );
             }
+
+            if premiums_available > 0 {
+                row.push(
+                    CreateButton::new(
+                        serde_json::to_string(&InteractionType {
+                            id,
+                            action: Action::Premium,
+                        })
+                        .unwrap(),
+                    )
+                    .label("Start new premium summon")
+                    .style(ButtonStyle::Success),
+                );
+            }
         }
         if !matches!(self.active, ActiveBanner::None) {
             row.push(
                 CreateButton::new(
                     serde_json::to_string(&InteractionType {
-                        order_number,
+                        id,
                         action: Action::Share,
                     })
                     .unwrap(),
@@ -208,22 +502,65 @@ impl PullsData {
                 .style(ButtonStyle::Secondary),
             );
         }
+        if wallet.rerolls > 0
+            && self
+                .active
+                .as_banner()
+                .is_some_and(|banner| banner.pulled() == 0)
+        {
+            row.push(
+                CreateButton::new(
+                    serde_json::to_string(&InteractionType {
+                        id,
+                        action: Action::Reroll,
+                    })
+                    .unwrap(),
+                )
+                .label(format!("Reroll this banner ({} left)", wallet.rerolls))
+                .style(ButtonStyle::Secondary),
+            );
+        }
+        if let Some(cost) = wallet.cost.filter(|cost| wallet.shards >= *cost)
+            && id.tracks_inventory()
+        {
+            row.push(
+                CreateButton::new(
+                    serde_json::to_string(&InteractionType {
+                        id,
+                        action: Action::Shards,
+                    })
+                    .unwrap(),
+                )
+                .label(format!("Spend {cost} shards on a single summon"))
+                .style(ButtonStyle::Success),
+            );
+        }
         if !row.is_empty() {
             buttons.push(CreateActionRow::Buttons(row));
         }
 
-        let image = match &self.active {
-            ActiveBanner::Single(banner) | ActiveBanner::Bulk(banner) => Some(banner.to_image()?),
-            _ => None,
+        let image = match self.active.as_banner() {
+            Some(banner) => Some(banner.to_image()?),
+            None => None,
         };
 
         let mut message = String::new();
-        writeln!(&mut message, "You are viewing **Order {order_number}**.")?;
+        writeln!(&mut message, "You are viewing **{}**.", id.label())?;
         writeln!(
             &mut message,
             "You have **{} full summons** and **{} single summons** remaining.",
             bulks_available, singles_available
         )?;
+        if self.premiums > 0 {
+            writeln!(
+                &mut message,
+                "You have **{} premium summons** remaining.",
+                premiums_available
+            )?;
+        }
+        if wallet.cost.is_some() {
+            writeln!(&mut message, "You have **{} shards**.", wallet.shards)?;
+        }
 
         if continue_banner.is_some() && start_banner {
             writeln!(
@@ -232,7 +569,12 @@ impl PullsData {
             )?;
         }
 
-        if continue_banner.is_some() {
+        if continue_banner.is_some() && matches!(self.active, ActiveBanner::Premium(..)) {
+            writeln!(
+                &mut message,
+                "You have started a premium summon. Choose one to summon."
+            )?;
+        } else if continue_banner.is_some() {
             writeln!(
                 &mut message,
                 "You have started a full summon. Choose an option to continue."
@@ -248,6 +590,19 @@ impl PullsData {
             )?;
         }
 
+        if let Some(seed) = self.seeds.last().filter(|seed| !seed.revealed) {
+            writeln!(
+                &mut message,
+                "-# Summons are provably fair: server seed hash `{}`, next nonce {}. Use `/verify{}` to reveal it once you are done.",
+                seed.hash,
+                self.nonce,
+                match id {
+                    RecordId::Daily(..) => " daily:True",
+                    _ => "",
+                }
+            )?;
+        }
+
         if let Some(extra) = extra {
             writeln!(&mut message, "\n{extra}")?;
         }
@@ -259,6 +614,93 @@ impl PullsData {
         })
     }
 
+    /// Shows one of this order's banners, oldest first, with buttons to page through the rest.
+    pub fn to_history_message(&self, id: RecordId, page: usize) -> Result<Message, crate::Error> {
+        let mut banners: Vec<_> = self
+            .bulk_pulls
+            .iter()
+            .map(|banner| ("Full summon", banner))
+            .chain(
+                self.single_pulls
+                    .iter()
+                    .map(|banner| ("Single summon", banner)),
+            )
+            .chain(
+                self.premium_pulls
+                    .iter()
+                    .map(|banner| ("Premium summon", banner)),
+            )
+            .chain(match &self.active {
+                ActiveBanner::Single(banner) => Some(("Single summon (current)", banner)),
+                ActiveBanner::Bulk(banner) => Some(("Full summon (current)", banner)),
+                ActiveBanner::Premium(banner) => Some(("Premium summon (current)", banner)),
+                ActiveBanner::None => None,
+            })
+            .collect();
+        // Banners from before proofs were recorded have no nonce, and come first.
+        banners.sort_by_key(|(_, banner)| banner.proof.as_ref().map(|proof| proof.nonce));
+
+        let Some((kind, banner)) = banners.get(page) else {
+            return Err(CustomError(format!("There are no summons on {} yet.", id.label())).into());
+        };
+
+        let mut message = String::new();
+        writeln!(
+            &mut message,
+            "**{}**, banner {} of {}: {}",
+            id.label(),
+            page + 1,
+            banners.len(),
+            kind
+        )?;
+        if let Some(updated_at) = banner.updated_at {
+            writeln!(
+                &mut message,
+                "Last summoned from <t:{}:f>.",
+                updated_at.unix_timestamp()
+            )?;
+        }
+        let names: Vec<_> = banner
+            .pulled_products()
+            .map(|product| product.name.as_str())
+            .collect();
+        if names.is_empty() {
+            writeln!(&mut message, "Nothing was summoned from this banner.")?;
+        } else {
+            writeln!(&mut message, "Summoned: {}", names.join(", "))?;
+        }
+
+        let button = |label: &str, page: usize| {
+            CreateButton::new(
+                serde_json::to_string(&InteractionType {
+                    id,
+                    action: Action::History(page),
+                })
+                .unwrap(),
+            )
+            .label(label)
+            .style(ButtonStyle::Secondary)
+        };
+        let mut row = vec![];
+        if page > 0 {
+            row.push(button("Previous", page - 1));
+        }
+        if page + 1 < banners.len() {
+            row.push(button("Next", page + 1));
+        }
+        let buttons = if row.is_empty() {
+            vec![]
+        } else {
+            vec![CreateActionRow::Buttons(row)]
+        };
+
+        Ok(Message {
+            message,
+            buttons,
+            image: Some(banner.to_image()?),
+        })
+    }
+
     pub fn into_share_message(
         self,
         discord_reference: String,
@@ -276,6 +718,17 @@ impl PullsData {
         }
     }
 
+    /// Spends shards on an extra single summon.
+    pub fn buy_single(&mut self, shards: &mut usize, cost: usize) -> Result<(), CustomError> {
+        *shards = shards.checked_sub(cost).ok_or_else(|| {
+            CustomError(format!(
+                "You need {cost} shards to buy a single summon, but only have {shards}."
+            ))
+        })?;
+        self.singles += 1;
+        Ok(())
+    }
+
     pub fn pull_slot(&mut self, slot: usize) -> Result<(), CustomError> {
         let pulled_singles = self.pulled_singles();
         match &mut self.active {
@@ -289,13 +742,24 @@ impl PullsData {
                     "This summon is already complete. Start a new one.".to_owned(),
                 ));
             }
-            ActiveBanner::Single(banner) | ActiveBanner::Bulk(banner) if banner.revealed[slot] => {
+            ActiveBanner::Premium(banner) if banner.pulled() > 0 => {
+                return Err(CustomError(
+                    "This summon is already complete. Start a new one.".to_owned(),
+                ));
+            }
+            ActiveBanner::Single(banner)
+            | ActiveBanner::Bulk(banner)
+            | ActiveBanner::Premium(banner)
+                if banner.revealed[slot] =>
+            {
                 return Err(CustomError(
                     "This hero has already been summoned.".to_owned(),
                 ));
             }
-            ActiveBanner::Single(banner) | ActiveBanner::Bulk(banner) => {
-                banner.revealed[slot] = true;
+            ActiveBanner::Single(banner)
+            | ActiveBanner::Bulk(banner)
+            | ActiveBanner::Premium(banner) => {
+                banner.reveal(slot);
             }
             ActiveBanner::None => {
                 return Err(CustomError(
@@ -306,14 +770,30 @@ impl PullsData {
         Ok(())
     }
 
+    /// The slots of the active full summon which are yet to be revealed, optionally only those
+    /// from one pool.
+    pub fn remaining_slots(&self, pool: Option<Pool>) -> Vec<usize> {
+        match &self.active {
+            ActiveBanner::Bulk(banner) => (0..5)
+                .filter(|i| !banner.revealed[*i])
+                .filter(|i| pool.is_none_or(|pool| banner.slots[*i].pool == pool))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     pub fn check_slot(&self, slot: usize) -> Option<&Product> {
         Some(&self.active.as_banner()?.slots[slot])
     }
 
     pub fn start_banner_single(
         &mut self,
+        id: RecordId,
         products: &Products,
         inventory: &HashMap<String, usize>,
+        pulled: &PullCounts,
+        wishlist: &[String],
+        rng: &mut impl Rng,
     ) -> Result<(), CustomError> {
         let pulls_remaining = self.singles - self.pulled_singles();
         if pulls_remaining == 0 {
@@ -331,15 +811,29 @@ impl PullsData {
                         .to_owned(),
                 ));
             }
-            _ => self.set_active(ActiveBanner::Single(products.banner(inventory))),
+            ActiveBanner::Premium(banner) if banner.pulled() == 0 => {
+                return Err(CustomError(
+                    "There is a premium summon in progress already, which must be completed first"
+                        .to_owned(),
+                ));
+            }
+            _ => {
+                let banner =
+                    self.generate_banner(id, products, None, inventory, pulled, wishlist, rng)?;
+                self.set_active(ActiveBanner::Single(banner))
+            }
         }
         Ok(())
     }
 
     pub fn start_banner_bulk(
         &mut self,
+        id: RecordId,
         products: &Products,
         inventory: &HashMap<String, usize>,
+        pulled: &PullCounts,
+        wishlist: &[String],
+        rng: &mut impl Rng,
     ) -> Result<(), CustomError> {
         let pulls_remaining = self.bulks - self.bulk_pulls.len();
         if pulls_remaining == 0 {
@@ -360,16 +854,718 @@ impl PullsData {
                         .to_owned(),
                 ));
             }
-            _ => self.set_active(ActiveBanner::Bulk(products.banner(inventory))),
+            ActiveBanner::Premium(banner) if banner.pulled() == 0 => {
+                return Err(CustomError(
+                    "There is a premium summon in progress already, which must be completed first"
+                        .to_owned(),
+                ));
+            }
+            _ => {
+                let banner =
+                    self.generate_banner(id, products, None, inventory, pulled, wishlist, rng)?;
+                self.set_active(ActiveBanner::Bulk(banner))
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a premium summon, from which a single product is drawn from the premium pools.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_banner_premium(
+        &mut self,
+        id: RecordId,
+        products: &Products,
+        premium: &Premium,
+        inventory: &HashMap<String, usize>,
+        pulled: &PullCounts,
+        wishlist: &[String],
+        rng: &mut impl Rng,
+    ) -> Result<(), CustomError> {
+        if self.premiums == self.pulled_premiums() {
+            return Err(CustomError(
+                "There are no more premium summons available for this order".to_owned(),
+            ));
+        }
+        match &self.active {
+            ActiveBanner::Single(banner) if banner.pulled() == 0 => {
+                return Err(CustomError(
+                    "There is a single summon in progress already, which must be completed first"
+                        .to_owned(),
+                ));
+            }
+            ActiveBanner::Bulk(banner) if banner.pulled() != 5 => {
+                return Err(CustomError(
+                    "There is a bulk summon in progress already, which must be completed first"
+                        .to_owned(),
+                ));
+            }
+            ActiveBanner::Premium(banner) if banner.pulled() == 0 => {
+                return Err(CustomError(
+                    "There is a premium summon in progress already, which must be completed first"
+                        .to_owned(),
+                ));
+            }
+            _ => {
+                let banner = self.generate_banner(
+                    id,
+                    products,
+                    Some(premium),
+                    inventory,
+                    pulled,
+                    wishlist,
+                    rng,
+                )?;
+                self.set_active(ActiveBanner::Premium(banner))
+            }
         }
         Ok(())
     }
 
+    /// Spends a reroll token to discard the active banner, so long as nothing has been summoned
+    /// from it yet, and replaces it with a new one of the same kind. The discarded banner no
+    /// longer holds any stock.
+    #[allow(clippy::too_many_arguments)]
+    pub fn reroll(
+        &mut self,
+        rerolls: &mut usize,
+        id: RecordId,
+        products: &Products,
+        inventory: &HashMap<String, usize>,
+        pulled: &PullCounts,
+        wishlist: &[String],
+        rng: &mut impl Rng,
+    ) -> Result<(), CustomError> {
+        if *rerolls == 0 {
+            return Err(CustomError("You do not have any rerolls.".to_owned()));
+        }
+        if self
+            .active
+            .as_banner()
+            .is_none_or(|banner| banner.pulled() != 0)
+        {
+            return Err(CustomError(
+                "Only a banner which has not been summoned from yet may be rerolled.".to_owned(),
+            ));
+        }
+        let premium = match &self.active {
+            ActiveBanner::Premium(..) => Some(products.premium.as_ref().ok_or_else(|| {
+                CustomError("Premium summons are not available right now.".to_owned())
+            })?),
+            _ => None,
+        };
+        let banner =
+            self.generate_banner(id, products, premium, inventory, pulled, wishlist, rng)?;
+        let discarded = match &mut self.active {
+            ActiveBanner::Single(active)
+            | ActiveBanner::Bulk(active)
+            | ActiveBanner::Premium(active) => std::mem::replace(active, banner),
+            ActiveBanner::None => unreachable!("checked that there is an active banner"),
+        };
+        self.rerolled.push(discarded);
+        *rerolls -= 1;
+        Ok(())
+    }
+
+    /// Generates a banner from the products the user may still summon, drawing only from the
+    /// premium pools if given.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_banner(
+        &mut self,
+        id: RecordId,
+        products: &Products,
+        premium: Option<&Premium>,
+        inventory: &HashMap<String, usize>,
+        pulled: &PullCounts,
+        wishlist: &[String],
+        rng: &mut impl Rng,
+    ) -> Result<Banner, CustomError> {
+        let allowances = |pulled: &PullCounts| {
+            let allowances = products.allowances(inventory, pulled);
+            match premium {
+                Some(premium) => premium.restrict(products, allowances),
+                None => allowances,
+            }
+        };
+        let candidates = products.candidates(&allowances(pulled), inventory, wishlist);
+        let nonce = self.nonce;
+        let mut rng = self.commit_seed(rng).rng(id, nonce);
+        let mut banner = products.banner(&candidates, &mut rng).ok_or_else(|| {
+            // Trying again later will not help a user who has reached their own limits.
+            let shared = allowances(&pulled.shared());
+            if shared.values().sum::<usize>() >= 5 {
+                limit_reached()
+            } else {
+                sold_out()
+            }
+        })?;
+        banner.proof = Some(Proof::new(
+            self.seeds.len() - 1,
+            nonce,
+            products,
+            &candidates,
+        ));
+        self.nonce += 1;
+        Ok(banner)
+    }
+
+    /// Reveals the current server seed, so that the banners generated from it can be verified,
+    /// and commits to a new one for any further banners.
+    ///
+    /// The seed determines the contents of every slot, so it may not be revealed while there
+    /// is a full or premium summon in progress, and revealing it ends any single summon in progress.
+    pub fn reveal_seed(&mut self, rng: &mut impl Rng) -> Result<(), CustomError> {
+        if matches!(&self.active, ActiveBanner::Bulk(banner) if banner.pulled() != 5)
+            || matches!(&self.active, ActiveBanner::Premium(banner) if banner.pulled() == 0)
+        {
+            return Err(CustomError(
+                "There is a summon in progress, which must be completed before its seed can be revealed."
+                    .to_owned(),
+            ));
+        }
+        let current = self.seeds.len().checked_sub(1).filter(|current| {
+            !self.seeds[*current].revealed
+                && self
+                    .banners()
+                    .filter_map(|banner| banner.proof.as_ref())
+                    .any(|proof| proof.seed == *current)
+        });
+        let Some(current) = current else {
+            return Err(CustomError(
+                "There are no summons on this order waiting to be verified.".to_owned(),
+            ));
+        };
+        if matches!(self.active, ActiveBanner::Single(..)) {
+            self.set_active(ActiveBanner::None);
+        }
+        self.seeds[current].revealed = true;
+        self.commit_seed(rng);
+        Ok(())
+    }
+
+    /// Summarises the result of reproducing each banner whose seed has been revealed. The
+    /// summary is the same length however many banners there are, as the details are attached
+    /// separately.
+    pub fn verification(&self, id: RecordId, products: &Products) -> Result<String, crate::Error> {
+        let (mut reproduced, mut failed) = (0, 0);
+        for banner in self.banners() {
+            let Some(proof) = &banner.proof else {
+                continue;
+            };
+            let Some(seed) = self.seeds.get(proof.seed).filter(|seed| seed.revealed) else {
+                continue;
+            };
+            let matches = proof
+                .candidates(products)
+                .and_then(|candidates| draw(&candidates, &mut seed.rng(id, proof.nonce)))
+                .is_some_and(|drawn| drawn.iter().zip(&banner.slots).all(|(a, b)| *a == b.sku));
+            if matches {
+                reproduced += 1;
+            } else {
+                failed += 1;
+            }
+        }
+
+        let mut message = String::new();
+        writeln!(
+            &mut message,
+            "Banners for **{}** are generated using ChaCha20, seeded with `SHA-256(\"{{server seed}}:{id}:{{nonce}}\")`. The attached file lists every revealed server seed and everything each banner was drawn from, so you can reproduce them yourself.",
+            id.label(),
+        )?;
+        if let Some(seed) = self.seeds.iter().rev().find(|seed| seed.revealed) {
+            writeln!(
+                &mut message,
+                "\nThe server seed just revealed is `{}`, with SHA-256 hash `{}`.",
+                seed.secret().unwrap_or_default(),
+                seed.hash
+            )?;
+        }
+        writeln!(&mut message, "✅ {reproduced} banners were reproduced.")?;
+        if failed > 0 {
+            writeln!(&mut message, "❌ {failed} banners could not be reproduced.")?;
+        }
+        if let Some(seed) = self.seeds.last().filter(|seed| !seed.revealed) {
+            writeln!(
+                &mut message,
+                "\nFurther summons will use the server seed with SHA-256 hash `{}`.",
+                seed.hash
+            )?;
+        }
+        Ok(message)
+    }
+
+    /// The inputs and results of each banner whose seed has been revealed, so that anyone can
+    /// reproduce them without relying on this bot.
+    pub fn proofs(&self, id: RecordId, products: &Products) -> serde_json::Value {
+        let seeds: Vec<_> = self
+            .seeds
+            .iter()
+            .enumerate()
+            .filter_map(|(index, seed)| {
+                let secret = seed.secret()?;
+                let mut proven: Vec<_> = self
+                    .banners()
+                    .filter_map(|banner| Some((banner, banner.proof.as_ref()?)))
+                    .filter(|(_, proof)| proof.seed == index)
+                    .collect();
+                proven.sort_by_key(|(_, proof)| proof.nonce);
+                let banners: Vec<_> = proven
+                    .into_iter()
+                    .map(|(banner, proof)| {
+                        json!({
+                            "nonce": proof.nonce,
+                            "rng_seed": format!("{secret}:{id}:{}", proof.nonce),
+                            "candidates_hash": proof.hash,
+                            "candidates": proof.candidates(products),
+                            "slots": banner.slots.iter().map(|product| &product.sku).collect::<Vec<_>>(),
+                        })
+                    })
+                    .collect();
+                Some(json!({
+                    "secret": secret,
+                    "hash": seed.hash,
+                    "banners": banners,
+                }))
+            })
+            .collect();
+        json!({
+            "record": id.to_string(),
+            "algorithm": "The RNG for each banner is ChaCha20 (rand_chacha 0.3) seeded with the SHA-256 of its rng_seed. \
+                Each of the 5 slots is drawn using rand 0.8's WeightedIndex over the weights of the candidates, in the order listed, \
+                which have not been drawn yet, or if there are none left, which have been drawn fewer times than their allowance. \
+                candidates_hash is the SHA-256 of the candidates as compact JSON, recorded when the banner was drawn. \
+                The candidates are null if the catalogue has changed since in a way which means they cannot be worked out again.",
+            "seeds": seeds,
+        })
+    }
+
+    /// This data as JSON for staff, without the secrets of unrevealed server seeds, which would
+    /// let anyone who sees them predict what is left in the current banners.
+    pub fn to_redacted_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap();
+        for seed in value["seeds"].as_array_mut().into_iter().flatten() {
+            if seed["revealed"] != true
+                && let Some(seed) = seed.as_object_mut()
+            {
+                seed.remove("secret");
+            }
+        }
+        value
+    }
+
+    /// Describes the state of these summons in full, for staff.
+    pub fn inspection(&self) -> Result<String, std::fmt::Error> {
+        let mut message = String::new();
+        writeln!(
+            &mut message,
+            "Singles: {} of {} used. Full: {} of {} used. Premium: {} of {} used.",
+            self.pulled_singles(),
+            self.singles,
+            self.pulled_bulks(),
+            self.bulks,
+            self.pulled_premiums(),
+            self.premiums,
+        )?;
+        let active = match &self.active {
+            ActiveBanner::Single(banner) => Some(("Single", banner)),
+            ActiveBanner::Bulk(banner) => Some(("Full", banner)),
+            ActiveBanner::Premium(banner) => Some(("Premium", banner)),
+            ActiveBanner::None => None,
+        };
+        match active {
+            Some((kind, banner)) => {
+                writeln!(
+                    &mut message,
+                    "\n**Active {kind} banner**{}",
+                    describe(banner)
+                )?;
+                if self.is_reserved() {
+                    writeln!(&mut message, "Its unrevealed products are held in reserve.")?;
+                }
+            }
+            None => writeln!(&mut message, "\nThere is no active banner.")?,
+        }
+        for (kind, banners) in [
+            ("Single", &self.single_pulls),
+            ("Full", &self.bulk_pulls),
+            ("Premium", &self.premium_pulls),
+        ] {
+            for (index, banner) in banners.iter().enumerate() {
+                writeln!(&mut message, "{kind} #{}{}", index + 1, describe(banner))?;
+            }
+        }
+        if !self.rerolled.is_empty() {
+            writeln!(
+                &mut message,
+                "\n{} banners were discarded.",
+                self.rerolled.len()
+            )?;
+        }
+        if !self.audit.is_empty() {
+            writeln!(&mut message, "\n**Changes**")?;
+            for entry in &self.audit {
+                writeln!(
+                    &mut message,
+                    "- <t:{}:f> by {}: {}",
+                    entry.at.unix_timestamp(),
+                    entry.by,
+                    serde_json::to_string(&entry.change).unwrap()
+                )?;
+            }
+        }
+        Ok(message)
+    }
+
+    /// Clears the active banner. If nothing has been summoned from it yet, it is discarded
+    /// without using up a summon, otherwise it is moved to the history as it is.
+    pub fn reset(&mut self) -> Result<(), CustomError> {
+        let Some(banner) = self.active.as_banner() else {
+            return Err(CustomError(
+                "There is no active banner to reset.".to_owned(),
+            ));
+        };
+        if banner.pulled() > 0 {
+            self.set_active(ActiveBanner::None);
+            return Ok(());
+        }
+        if let ActiveBanner::Single(banner)
+        | ActiveBanner::Bulk(banner)
+        | ActiveBanner::Premium(banner) = std::mem::replace(&mut self.active, ActiveBanner::None)
+        {
+            self.rerolled.push(banner);
+        }
+        Ok(())
+    }
+
+    /// Undoes a revealed slot of a banner, the active one if no index is given, returning the
+    /// product which had been summoned. A voided single summon may be used again. Products which
+    /// have been traded away cannot be voided, as the other user still receives them.
+    pub fn void(
+        &mut self,
+        kind: BannerKind,
+        index: Option<usize>,
+        slot: usize,
+    ) -> Result<Product, CustomError> {
+        let banner = self
+            .banner_mut(kind, index)
+            .ok_or_else(|| CustomError("There is no such banner.".to_owned()))?;
+        if !banner.revealed.get(slot).copied().unwrap_or(false) {
+            return Err(CustomError("That slot has not been summoned.".to_owned()));
+        }
+        let product = banner.slots[slot].clone();
+        if !self.owned_products().any(|owned| owned.sku == product.sku) {
+            return Err(CustomError(format!(
+                "{} has been traded away, so this pull cannot be voided.",
+                product.name
+            )));
+        }
+        self.banner_mut(kind, index).unwrap().void(slot);
+        Ok(product)
+    }
+
+    /// A banner of the given kind, the active one if no index is given.
+    fn banner_mut(&mut self, kind: BannerKind, index: Option<usize>) -> Option<&mut Banner> {
+        match (kind, index, &mut self.active) {
+            (BannerKind::Single, None, ActiveBanner::Single(banner))
+            | (BannerKind::Bulk, None, ActiveBanner::Bulk(banner))
+            | (BannerKind::Premium, None, ActiveBanner::Premium(banner)) => Some(banner),
+            (_, None, _) => None,
+            (BannerKind::Single, Some(index), _) => self.single_pulls.get_mut(index),
+            (BannerKind::Bulk, Some(index), _) => self.bulk_pulls.get_mut(index),
+            (BannerKind::Premium, Some(index), _) => self.premium_pulls.get_mut(index),
+        }
+    }
+
     fn set_active(&mut self, banner: ActiveBanner) {
         match std::mem::replace(&mut self.active, banner) {
             ActiveBanner::Single(banner) => self.single_pulls.push(banner),
             ActiveBanner::Bulk(banner) => self.bulk_pulls.push(banner),
+            ActiveBanner::Premium(banner) => self.premium_pulls.push(banner),
             ActiveBanner::None => {}
         }
     }
 }
+
+fn describe(banner: &Banner) -> String {
+    let mut description = String::new();
+    if let Some(updated_at) = banner.updated_at {
+        description.push_str(&format!(" (<t:{}:f>)", updated_at.unix_timestamp()));
+    }
+    description.push(':');
+    for (slot, (product, revealed)) in banner.slots.iter().zip(banner.revealed).enumerate() {
+        description.push_str(&format!(
+            " {}{} {} ({}){}",
+            if revealed { "**" } else { "" },
+            slot + 1,
+            product.name,
+            product.pool,
+            if revealed { "**" } else { "" },
+        ));
+    }
+    description
+}
+
+fn pool_style(pool: Pool) -> ButtonStyle {
+    match pool {
+        Pool::Red => ButtonStyle::Danger,
+        Pool::Green => ButtonStyle::Success,
+        Pool::Blue => ButtonStyle::Primary,
+        Pool::White => ButtonStyle::Secondary,
+    }
+}
+
+fn sold_out() -> CustomError {
+    CustomError(
+        "The gacha machine is nearly sold out, so there is not enough left to start a new summon for you right now. Please try again later."
+            .to_owned(),
+    )
+}
+
+fn limit_reached() -> CustomError {
+    CustomError(
+        "You have summoned as many of some products as you are allowed to, so there are not enough products left for you to start a new summon."
+            .to_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn products() -> Products {
+        let products = (0..8)
+            .map(|i| {
+                format!("[[product]]\nname = \"Product {i}\"\nsku = \"SKU-{i}\"\npool = \"Red\"\nrarity = {}\n", i + 1)
+            })
+            .collect::<String>();
+        Products::from_toml(&products).unwrap()
+    }
+
+    fn bulk(rng_seed: u64) -> PullsData {
+        let products = products();
+        let mut pulls = PullsData::new(0, 1);
+        pulls
+            .start_banner_bulk(
+                "1234".parse().unwrap(),
+                &products,
+                &products.unlimited_stock(),
+                &PullCounts::default(),
+                &[],
+                &mut ChaCha20Rng::seed_from_u64(rng_seed),
+            )
+            .unwrap();
+        pulls
+    }
+
+    fn skus(pulls: &PullsData) -> Vec<&str> {
+        let banner = pulls.active.as_banner().unwrap();
+        banner.slots.iter().map(|slot| slot.sku.as_str()).collect()
+    }
+
+    #[test]
+    fn unrevealed_secrets_are_redacted() {
+        let mut pulls = bulk(7);
+        let redacted = pulls.to_redacted_json();
+        assert!(redacted["seeds"][0].get("secret").is_none());
+        assert!(redacted["seeds"][0].get("hash").is_some());
+
+        pulls.seeds[0].revealed = true;
+        let redacted = pulls.to_redacted_json();
+        assert!(redacted["seeds"][0].get("secret").is_some());
+    }
+
+    #[test]
+    fn banners_are_determined_by_the_injected_rng() {
+        assert_eq!(skus(&bulk(7)), skus(&bulk(7)));
+        assert_ne!(skus(&bulk(7)), skus(&bulk(8)));
+    }
+
+    #[test]
+    fn banners_are_reproduced_from_their_seed_and_proof() {
+        let pulls = bulk(7);
+        let proof = pulls.active.as_banner().unwrap().proof.as_ref().unwrap();
+        assert_eq!((proof.seed, proof.nonce), (0, 0));
+        let reproduced = draw(
+            &proof.candidates(&products()).unwrap(),
+            &mut pulls.seeds[0].rng("1234".parse().unwrap(), proof.nonce),
+        )
+        .unwrap();
+        assert_eq!(
+            reproduced.iter().map(String::as_str).collect::<Vec<_>>(),
+            skus(&pulls)
+        );
+    }
+
+    #[test]
+    fn banners_beyond_the_users_limits_are_not_sold_out() {
+        let inventory = products().unlimited_stock();
+        let one_each: HashMap<_, _> = (0..8).map(|i| (format!("SKU-{i}"), 1)).collect();
+        let start = |limit: &str, pulled: &PullCounts| {
+            let products = (0..8)
+                .map(|i| {
+                    format!("[[product]]\nname = \"Product {i}\"\nsku = \"SKU-{i}\"\npool = \"Red\"\nrarity = 1\n{limit} = 1\n")
+                })
+                .collect::<String>();
+            PullsData::new(0, 1)
+                .start_banner_bulk(
+                    "1234".parse().unwrap(),
+                    &Products::from_toml(&products).unwrap(),
+                    &inventory,
+                    pulled,
+                    &[],
+                    &mut ChaCha20Rng::seed_from_u64(7),
+                )
+                .map_err(|error| error.0)
+        };
+        let pulled = PullCounts {
+            order: one_each.clone(),
+            user: one_each.clone(),
+            total: one_each.clone(),
+        };
+        assert_eq!(start("max_per_order", &PullCounts::default()), Ok(()));
+        assert_eq!(start("max_per_order", &pulled), Err(limit_reached().0));
+        assert_eq!(start("max_per_user", &pulled), Err(limit_reached().0));
+        assert_eq!(start("max_total", &pulled), Err(sold_out().0));
+    }
+
+    #[test]
+    fn verification_stays_the_same_size() {
+        let products = products();
+        let id: RecordId = "1234".parse().unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let mut pulls = PullsData::new(50, 0);
+        let mut lengths = vec![];
+        for _ in 0..50 {
+            pulls
+                .start_banner_single(
+                    id,
+                    &products,
+                    &products.unlimited_stock(),
+                    &PullCounts::default(),
+                    &[],
+                    &mut rng,
+                )
+                .unwrap();
+            pulls.pull_slot(0).unwrap();
+            pulls.reveal_seed(&mut rng).unwrap();
+            let message = pulls.verification(id, &products).unwrap();
+            assert!(message.contains("✅ "));
+            assert!(!message.contains("❌"));
+            lengths.push(message.len());
+        }
+        // Only the banner count grows, by a digit.
+        assert!(lengths.iter().all(|length| *length < 2000));
+        assert!(lengths.last().unwrap() - lengths.first().unwrap() <= 1);
+    }
+
+    #[test]
+    fn banners_do_not_repeat_products_while_others_are_left() {
+        let pulls = bulk(7);
+        let distinct: std::collections::HashSet<_> = skus(&pulls).into_iter().collect();
+        assert_eq!(distinct.len(), 5);
+    }
+
+    #[test]
+    fn banners_need_five_units_in_stock() {
+        let products = products();
+        let mut inventory: HashMap<_, _> = [("SKU-0", 2), ("SKU-1", 2)]
+            .into_iter()
+            .map(|(sku, stock)| (sku.to_owned(), stock))
+            .collect();
+        let start = |inventory: &HashMap<String, usize>| {
+            let mut pulls = PullsData::new(0, 1);
+            pulls
+                .start_banner_bulk(
+                    "1234".parse().unwrap(),
+                    &products,
+                    inventory,
+                    &PullCounts::default(),
+                    &[],
+                    &mut ChaCha20Rng::seed_from_u64(7),
+                )
+                .map(|()| pulls)
+        };
+        assert!(start(&inventory).is_err());
+
+        inventory.insert("SKU-2".to_owned(), 1);
+        let pulls = start(&inventory).unwrap();
+        let mut skus = skus(&pulls);
+        skus.sort();
+        assert_eq!(skus, ["SKU-0", "SKU-0", "SKU-1", "SKU-1", "SKU-2"]);
+    }
+
+    #[test]
+    fn traded_products_cannot_be_voided() {
+        let mut pulls = bulk(7);
+        pulls.pull_slot(0).unwrap();
+        pulls.pull_slot(1).unwrap();
+        let traded = skus(&pulls)[0].to_owned();
+        let received = products()
+            .iter()
+            .find(|product| product.sku != traded)
+            .unwrap()
+            .clone();
+        pulls.trade(&traded, received).unwrap();
+
+        assert!(pulls.void(BannerKind::Bulk, None, 0).is_err());
+        assert!(pulls.active.as_banner().unwrap().revealed[0]);
+        assert_eq!(
+            pulls.void(BannerKind::Bulk, None, 1).unwrap().sku,
+            skus(&pulls)[1]
+        );
+    }
+
+    fn product(sku: &str) -> Product {
+        products()
+            .iter()
+            .find(|product| product.sku == sku)
+            .unwrap()
+            .clone()
+    }
+
+    /// An order which has summoned the given products.
+    fn owning(skus: [&str; 5]) -> PullsData {
+        let mut banner = Banner::new(skus.map(product));
+        banner.revealed = [true; 5];
+        let mut pulls = PullsData::new(0, 1);
+        pulls.bulk_pulls.push(banner);
+        pulls
+    }
+
+    fn owned(pulls: &PullsData) -> Vec<String> {
+        let mut owned: Vec<_> = pulls.owned_skus().collect();
+        owned.sort();
+        owned
+    }
+
+    #[test]
+    fn trading_away_a_duplicate_keeps_the_other_unit() {
+        let mut pulls = owning(["SKU-0", "SKU-0", "SKU-1", "SKU-2", "SKU-3"]);
+        pulls.trade("SKU-0", product("SKU-4")).unwrap();
+        assert_eq!(owned(&pulls), ["SKU-0", "SKU-1", "SKU-2", "SKU-3", "SKU-4"]);
+
+        pulls.trade("SKU-0", product("SKU-5")).unwrap();
+        assert_eq!(owned(&pulls), ["SKU-1", "SKU-2", "SKU-3", "SKU-4", "SKU-5"]);
+    }
+
+    #[test]
+    fn units_traded_in_can_be_traded_away() {
+        let mut pulls = owning(["SKU-0", "SKU-1", "SKU-2", "SKU-3", "SKU-4"]);
+        pulls.trade("SKU-0", product("SKU-5")).unwrap();
+        pulls.trade("SKU-5", product("SKU-6")).unwrap();
+        assert_eq!(owned(&pulls), ["SKU-1", "SKU-2", "SKU-3", "SKU-4", "SKU-6"]);
+        // Products traded away still count towards limits.
+        assert!(pulls.skus().any(|sku| sku == "SKU-0"));
+    }
+
+    #[test]
+    fn trades_refuse_products_which_are_not_owned() {
+        let mut pulls = owning(["SKU-0", "SKU-1", "SKU-2", "SKU-3", "SKU-4"]);
+        assert!(pulls.trade("SKU-7", product("SKU-5")).is_err());
+        pulls.trade("SKU-0", product("SKU-5")).unwrap();
+        assert!(pulls.trade("SKU-0", product("SKU-6")).is_err());
+        assert_eq!(pulls.traded_out, ["SKU-0"]);
+        assert_eq!(owned(&pulls), ["SKU-1", "SKU-2", "SKU-3", "SKU-4", "SKU-5"]);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::shopify::OrderNumber;
+use poise::serenity_prelude::UserId;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Identifies a set of summons: either those purchased on a Shopify order, or those given
+/// directly to a Discord user.
+///
+/// Order numbers serialize as plain numbers, the same as an [`OrderNumber`], while the others
+/// serialize as strings.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum RecordId {
+    Order(OrderNumber),
+    /// Bonus summons, such as from promo codes.
+    User(UserId),
+    /// Daily free summons.
+    Daily(UserId),
+}
+
+impl RecordId {
+    pub fn order_number(&self) -> Option<OrderNumber> {
+        match self {
+            Self::Order(order_number) => Some(*order_number),
+            Self::User(..) | Self::Daily(..) => None,
+        }
+    }
+
+    /// Whether the products summoned are tracked in the shop's inventory. Daily summons have
+    /// their own products, which are not.
+    pub fn tracks_inventory(&self) -> bool {
+        !matches!(self, Self::Daily(..))
+    }
+
+    /// A description of these summons for display to the user.
+    pub fn label(&self) -> String {
+        match self {
+            Self::Order(order_number) => format!("Order {order_number}"),
+            Self::User(..) => "your bonus summons".to_owned(),
+            Self::Daily(..) => "your daily summons".to_owned(),
+        }
+    }
+}
+
+impl From<OrderNumber> for RecordId {
+    fn from(order_number: OrderNumber) -> Self {
+        Self::Order(order_number)
+    }
+}
+
+impl FromStr for RecordId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(s) = s.strip_prefix('@') {
+            Ok(Self::User(s.parse().map_err(|_| ())?))
+        } else if let Some(s) = s.strip_prefix('~') {
+            Ok(Self::Daily(s.parse().map_err(|_| ())?))
+        } else {
+            Ok(Self::Order(s.parse().map_err(|_| ())?))
+        }
+    }
+}
+
+impl Display for RecordId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Order(order_number) => order_number.fmt(f),
+            Self::User(user_id) => write!(f, "@{user_id}"),
+            Self::Daily(user_id) => write!(f, "~{user_id}"),
+        }
+    }
+}
+
+impl Serialize for RecordId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Order(order_number) => order_number.serialize(serializer),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RecordId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Order(OrderNumber),
+            Other(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Order(order_number) => Ok(Self::Order(order_number)),
+            Repr::Other(id) => id
+                .parse()
+                .map_err(|_| serde::de::Error::custom(format!("invalid record ID {id}"))),
+        }
+    }
+}
@@ -1,9 +1,9 @@
-use super::PullsData;
-use crate::shopify::OrderNumber;
+use super::{Change, PullsData, RecordId};
+use poise::serenity_prelude::User;
 
 #[derive(Debug)]
 pub struct Row {
-    pub order_number: OrderNumber,
+    pub id: RecordId,
     pub discord_user_id: String,
     pub discord_username: String,
     pub pulls: PullsData,
@@ -13,13 +13,13 @@ pub struct Row {
 
 impl Row {
     pub fn new(
-        order_number: OrderNumber,
+        id: RecordId,
         discord_user_id: String,
         discord_username: String,
         pulls: PullsData,
     ) -> Self {
         Self {
-            order_number,
+            id,
             discord_user_id,
             discord_username,
             pulls,
@@ -27,14 +27,31 @@ impl Row {
         }
     }
 
+    /// Reassigns the summons to another user.
+    pub fn transfer(&mut self, to: &User, by: String, reason: String) {
+        let from = std::mem::replace(&mut self.discord_user_id, to.id.to_string());
+        self.discord_username = to.name.to_owned();
+        self.pulls.record(
+            by,
+            Change::Transfer {
+                from,
+                to: self.discord_user_id.clone(),
+                reason,
+            },
+        );
+    }
+
     pub(super) fn into_cells(self) -> Vec<String> {
         let mut values = vec![
-            self.order_number.to_string(),
+            self.id.to_string(),
             self.discord_user_id,
             self.discord_username,
             serde_json::to_string(&self.pulls).unwrap(),
         ];
+        let width = values.len() + self.pulls.names_written();
         values.extend(self.pulls.names());
+        // Names may have been removed since the row was last written, so clear any left over.
+        values.resize(width.max(values.len()), String::new());
         values
     }
 }
@@ -45,7 +62,7 @@ impl TryFrom<Vec<String>> for Row {
     fn try_from(row: Vec<String>) -> Result<Self, Self::Error> {
         let mut row = row.into_iter();
         Ok(Self {
-            order_number: row.next().ok_or(())?.parse().map_err(|_| ())?,
+            id: row.next().ok_or(())?.parse().map_err(|_| ())?,
             discord_user_id: row.next().ok_or(())?,
             discord_username: row.next().ok_or(())?,
             pulls: serde_json::from_str(&row.next().ok_or(())?).map_err(|_| ())?,
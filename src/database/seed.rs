@@ -0,0 +1,74 @@
+use super::RecordId;
+use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// A server seed, from which the banners of an order are generated.
+///
+/// Only the hash of the seed is shown until it is revealed, at which point anyone can check
+/// that the banners generated from it were not tampered with: the RNG for each banner is
+/// ChaCha20 seeded with `SHA-256("{secret}:{record_id}:{nonce}")`, where the record ID is the
+/// order number (e.g. `#1234`) for summons bought on an order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Seed {
+    secret: String,
+    pub hash: String,
+    #[serde(default)]
+    pub revealed: bool,
+}
+
+impl Seed {
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let secret = rng
+            .r#gen::<[u8; 32]>()
+            .iter()
+            .fold(String::new(), |mut secret, byte| {
+                write!(secret, "{byte:02x}").unwrap();
+                secret
+            });
+        let hash = format!("{:x}", Sha256::digest(&secret));
+        Self {
+            secret,
+            hash,
+            revealed: false,
+        }
+    }
+
+    /// The secret, which is only available once the seed has been revealed.
+    pub fn secret(&self) -> Option<&str> {
+        self.revealed.then_some(&self.secret)
+    }
+
+    pub fn rng(&self, id: RecordId, nonce: usize) -> ChaCha20Rng {
+        let seed = Sha256::digest(format!("{}:{}:{}", self.secret, id, nonce));
+        ChaCha20Rng::from_seed(seed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_come_from_the_injected_rng() {
+        let a = Seed::generate(&mut ChaCha20Rng::seed_from_u64(7));
+        let b = Seed::generate(&mut ChaCha20Rng::seed_from_u64(7));
+        let c = Seed::generate(&mut ChaCha20Rng::seed_from_u64(8));
+        assert_eq!(a.secret, b.secret);
+        assert_ne!(a.secret, c.secret);
+        assert_eq!(a.hash, format!("{:x}", Sha256::digest(&a.secret)));
+        assert_eq!(a.secret(), None);
+    }
+
+    #[test]
+    fn banner_rng_is_derived_from_secret_record_and_nonce() {
+        let seed = Seed::generate(&mut ChaCha20Rng::seed_from_u64(7));
+        let id: RecordId = "1234".parse().unwrap();
+        let expected = Sha256::digest(format!("{}:#1234:3", seed.secret));
+        assert_eq!(seed.rng(id, 3), ChaCha20Rng::from_seed(expected.into()));
+        assert_ne!(seed.rng(id, 3), seed.rng(id, 4));
+        assert_ne!(seed.rng(id, 3), seed.rng("1235".parse().unwrap(), 3));
+    }
+}
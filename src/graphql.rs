@@ -1,10 +1,10 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::fmt::{self, Display};
 
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
 pub enum GraphQLResponse<T> {
-    Success { data: T, extensions: Value },
+    Success { data: T },
     Error { errors: Vec<GraphQLError> },
 }
 
@@ -17,7 +17,6 @@ pub struct Connection<T> {
 pub struct GraphQLError {
     pub message: Option<String>,
     pub locations: Option<Vec<Location>>,
-    pub extensions: Value,
 }
 
 #[derive(Deserialize, Debug)]
@@ -25,3 +24,13 @@ pub struct Location {
     pub line: u32,
     pub column: u32,
 }
+
+impl Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message.as_deref().unwrap_or("Unknown error"))?;
+        for location in self.locations.iter().flatten() {
+            write!(f, " (line {}, column {})", location.line, location.column)?;
+        }
+        Ok(())
+    }
+}
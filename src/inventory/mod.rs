@@ -1,4 +1,4 @@
-use crate::shopify::OrderNumber;
+use crate::database::RecordId;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -13,7 +13,8 @@ pub struct Client {
 #[derive(Deserialize, Serialize)]
 pub struct Item {
     sku: String,
-    quantity: usize,
+    /// Negative when returning stock.
+    quantity: i64,
 }
 
 #[derive(Serialize)]
@@ -36,16 +37,42 @@ impl Client {
     pub async fn log_pull(
         &self,
         discord_user_id: String,
-        order_number: OrderNumber,
+        id: RecordId,
+        skus: impl IntoIterator<Item = String>,
+    ) -> Result<(), reqwest::Error> {
+        let mut items = Vec::<Item>::new();
+        for sku in skus {
+            match items.iter_mut().find(|item| item.sku == sku) {
+                Some(item) => item.quantity += 1,
+                None => items.push(Item { sku, quantity: 1 }),
+            }
+        }
+        self.log_order(OrderData {
+            source: "Discord Gacha",
+            data: json! {{
+                "order_number": id.order_number(),
+                "discord_user_id": discord_user_id,
+            }},
+            items,
+        })
+        .await
+    }
+
+    /// Returns the stock of a pull which has been voided.
+    pub async fn log_void(
+        &self,
+        discord_user_id: String,
+        id: RecordId,
         sku: String,
     ) -> Result<(), reqwest::Error> {
         self.log_order(OrderData {
             source: "Discord Gacha",
             data: json! {{
-                "order_number": order_number,
+                "order_number": id.order_number(),
                 "discord_user_id": discord_user_id,
+                "void": true,
             }},
-            items: vec![Item { sku, quantity: 1 }],
+            items: vec![Item { sku, quantity: -1 }],
         })
         .await
     }
@@ -72,7 +99,7 @@ impl Client {
             .json::<Vec<Item>>()
             .await?
             .into_iter()
-            .map(|item| (item.sku, item.quantity))
+            .map(|item| (item.sku, item.quantity.max(0) as usize))
             .collect())
     }
 }
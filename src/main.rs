@@ -1,27 +1,87 @@
 use poise::BoxFuture;
 use poise::dispatch::FrameworkContext;
 use poise::serenity_prelude::{self as serenity, *};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use tokio::sync::Mutex;
 
+mod commands;
 mod config;
 mod database;
 mod error;
 mod graphql;
 mod inventory;
 mod shopify;
+mod staff;
+mod stats;
 
-use config::Products;
-use database::{PullsData, Row, Sheets};
+use config::{Pool, Product, Products, Set};
+use database::{Profile, PullsData, RecordId, Row, Sheets, Wallet};
 use error::CustomError;
-use shopify::OrderNumber;
+use staff::Staff;
 
 struct Data {
     shopify: shopify::Client,
     sheets: Sheets,
     products: Products,
     inventory: inventory::Client,
+    staff: Staff,
+    /// Held while loading records in order to save them again, so that concurrent updates are
+    /// not lost, and so that stock reserved by active banners is not promised twice.
+    lock: Mutex<()>,
+    /// The source of server seeds, from which all banners are generated.
+    rng: std::sync::Mutex<ChaCha20Rng>,
+    /// The statistics for each server, loaded the first time they are needed, then kept up to
+    /// date as products are pulled.
+    stats: std::sync::Mutex<HashMap<GuildId, stats::Stats>>,
+    /// Trades which are waiting to be confirmed.
+    trades: std::sync::Mutex<commands::Trades>,
+}
+
+impl Data {
+    /// The products which summons for a record are drawn from.
+    fn catalogue(&self, id: RecordId) -> Result<&Products, CustomError> {
+        match id {
+            RecordId::Daily(..) => self
+                .products
+                .daily
+                .as_ref()
+                .map(|daily| &*daily.products)
+                .ok_or_else(|| CustomError("Daily summons are no longer available.".to_owned())),
+            _ => Ok(&self.products),
+        }
+    }
+
+    /// Discards the statistics after a change which they are not kept up to date with, so that
+    /// they are loaded again when next needed.
+    fn forget_stats(&self) {
+        self.stats.lock().unwrap().clear();
+    }
+
+    fn wallet(&self, profile: &Profile) -> Wallet {
+        Wallet {
+            shards: profile.data.shards,
+            cost: self.products.shards.as_ref().map(|shards| shards.cost),
+            rerolls: profile.data.rerolls,
+        }
+    }
+
+    /// Retrieves the stock available to new banners for a record.
+    async fn stock(
+        &self,
+        ctx: &serenity::Context,
+        id: RecordId,
+        reserved: &HashMap<String, usize>,
+    ) -> Result<HashMap<String, usize>, CustomError> {
+        if id.tracks_inventory() {
+            get_inventory(ctx, self, reserved).await
+        } else {
+            Ok(self.catalogue(id)?.unlimited_stock())
+        }
+    }
 }
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -29,7 +89,8 @@ type Context<'a> = poise::Context<'a, Data, Error>;
 
 #[derive(Copy, Clone, Serialize, Deserialize, Debug)]
 struct InteractionType {
-    order_number: OrderNumber,
+    #[serde(rename = "order_number")]
+    id: RecordId,
     action: Action,
 }
 
@@ -39,81 +100,69 @@ enum Action {
     Bulk,
     Pull(usize),
     Share,
+    Shards,
+    Premium,
+    Reroll,
+    PullAll,
+    PullPool(Pool),
+    History(usize),
+    Resolve { claimant: UserId, approve: bool },
+    Trade { trade: u32, confirm: bool },
 }
 
-/// Summon enamel pins on Kittyalyst's gacha machine.
-///
-/// Requires a valid purchase order number from https://www.kittyalyst.com/products/kitty-emblem-gacha
-#[poise::command(slash_command, ephemeral)]
-async fn summon(
-    ctx: Context<'_>,
-    #[description = "Order Number"] order_number: OrderNumber,
-) -> Result<(), Error> {
-    let Context::Application(ctx) = ctx else {
-        unreachable!("Slash command is always application");
-    };
-
-    log::info!("User attempting pull: {}", order_number);
-    let data = ctx.data();
-    let order = data.shopify.get_order(order_number).await?;
-    log::debug!("Pulling for order: {:#?}", order);
-
-    let row = data.sheets.get_order(order_number).await?;
-    let row = match row {
-        Some(row) => row,
-        None => {
-            let (singles, bulks) = if std::env::var("PULLS_FREE").is_ok() {
-                (3, 2)
-            } else {
-                let tickets = data
-                    .products
-                    .ticket
-                    .iter()
-                    .map(|ticket| (&ticket.sku, (ticket.singles, ticket.bulks)))
-                    .collect::<HashMap<_, _>>();
-
-                order
-                    .line_items
-                    .nodes
-                    .iter()
-                    .filter_map(|product| {
-                        let (single, bulk) = tickets.get(&product.sku.as_ref()?)?;
-                        Some((single * product.quantity, bulk * product.quantity))
-                    })
-                    .fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
-            };
-
-            if singles == 0 && bulks == 0 {
-                log::warn!("Trying to pull {} with no purchased tickets", order_number);
-                ctx.say("There are no available summons associated with this order. Buy a summon for a valid order number: https://www.kittyalyst.com/products/kitty-emblem-gacha")
-                    .await?;
-                return Ok(());
-            } else {
-                Row::new(
-                    order_number,
-                    ctx.interaction.user.id.to_string(),
-                    ctx.interaction.user.name.to_owned(),
-                    PullsData::new(singles, bulks),
-                )
-            }
+/// Retrieves the stock which is available to new banners, that is the shop's inventory less
+/// anything already reserved by the active banners of other orders.
+async fn available_inventory(
+    data: &Data,
+    reserved: &HashMap<String, usize>,
+) -> Result<HashMap<String, usize>, CustomError> {
+    let mut inventory = data.inventory.get_inventory().await.map_err(|err| {
+        log::error!("Failed to check inventory: {}", err);
+        CustomError("Failed to check shop inventory, try again later.".to_owned())
+    })?;
+    for (sku, quantity) in reserved {
+        if let Some(stock) = inventory.get_mut(sku) {
+            *stock = stock.saturating_sub(*quantity);
         }
-    };
+    }
+    Ok(inventory)
+}
+
+/// Retrieves the stock which is available to new banners, alerting staff if the machine is
+/// running out.
+async fn get_inventory(
+    ctx: &serenity::Context,
+    data: &Data,
+    reserved: &HashMap<String, usize>,
+) -> Result<HashMap<String, usize>, CustomError> {
+    const SOLD_OUT: &str = "sold_out";
+    const LOW_STOCK: &str = "low_stock";
 
-    log::debug!("Pull state: {:#?}", row);
+    let inventory = available_inventory(data, reserved).await?;
 
-    if row.discord_user_id != ctx.interaction.user.id.to_string() {
-        log::warn!("Wrong user pulled for order {}", order_number);
-        ctx.say(
-            "This order number has already been summoned for by someone else. Are you sure it's yours?",
-        )
-        .await?;
-        return Ok(());
+    if data.products.units_in_stock(&inventory) < 5 {
+        data.staff
+            .alert_once(
+                &ctx.http,
+                SOLD_OUT,
+                "The gacha machine is sold out: there are fewer than 5 units left in stock, so no new summons can be started.",
+            )
+            .await;
+    } else if data.products.products_in_stock(&inventory) < 5 {
+        data.staff.clear(SOLD_OUT);
+        data.staff
+            .alert_once(
+                &ctx.http,
+                LOW_STOCK,
+                "The gacha machine is running low: there are fewer than 5 different products left in stock, so summons may contain duplicates.",
+            )
+            .await;
+    } else {
+        data.staff.clear(SOLD_OUT);
+        data.staff.clear(LOW_STOCK);
     }
-    let message = row.pulls.to_message(order_number, None)?;
-    ctx.send(message.into_reply()).await?;
-    data.sheets.save(row).await?;
 
-    Ok(())
+    Ok(inventory)
 }
 
 async fn handle_interaction(
@@ -123,67 +172,181 @@ async fn handle_interaction(
 ) -> Result<(), Error> {
     let interaction_id: InteractionType = serde_json::from_str(&interaction.data.custom_id)?;
     log::info!("Received interaction {:?}", interaction_id);
-    let mut row = data
-        .sheets
-        .get_order(interaction_id.order_number)
-        .await?
+    if let Action::Resolve { claimant, approve } = interaction_id.action {
+        return commands::resolve_dispute(
+            ctx,
+            interaction,
+            data,
+            interaction_id.id,
+            claimant,
+            approve,
+        )
+        .await;
+    }
+    if let Action::Trade { trade, confirm } = interaction_id.action {
+        return commands::respond_trade(ctx, interaction, data, interaction_id.id, trade, confirm)
+            .await;
+    }
+
+    let _guard = match interaction_id.action {
+        Action::Share | Action::History(..) => None,
+        _ => Some(data.lock.lock().await),
+    };
+    let mut database = data.sheets.database().await?;
+    let mut row = database
+        .remove(&interaction_id.id)
         .ok_or_else(|| CustomError("Pull data for this order could not be found.".to_owned()))?;
+    let reserved = database::reserved(database.values());
+    let pulled = database::pull_counts(&row, database.values());
+    let products = data.catalogue(row.id)?;
+    let mut profile = data.sheets.get_profile(&interaction.user).await?;
+    let wallet = data.wallet(&profile);
 
     let mut extra = None;
+    let mut completed = vec![];
+    let mut summoned = vec![];
+    let mut bought = None;
     match interaction_id.action {
         Action::Single => {
-            let inventory = data.inventory.get_inventory().await.map_err(|err| {
-                log::error!("Failed to check inventory: {}", err);
-                CustomError("Failed to check shop inventory, try again later.".to_owned())
-            })?;
-            row.pulls.start_banner_single(&data.products, &inventory)?;
+            let inventory = data.stock(ctx, row.id, &reserved).await?;
+            row.pulls.start_banner_single(
+                row.id,
+                products,
+                &inventory,
+                &pulled,
+                &profile.data.wishlist,
+                &mut *data.rng.lock().unwrap(),
+            )?;
         }
         Action::Bulk => {
-            let inventory = data.inventory.get_inventory().await.map_err(|err| {
-                log::error!("Failed to check inventory: {}", err);
-                CustomError("Failed to check shop inventory, try again later.".to_owned())
+            let inventory = data.stock(ctx, row.id, &reserved).await?;
+            row.pulls.start_banner_bulk(
+                row.id,
+                products,
+                &inventory,
+                &pulled,
+                &profile.data.wishlist,
+                &mut *data.rng.lock().unwrap(),
+            )?;
+        }
+        Action::Premium => {
+            let premium = products.premium.as_ref().ok_or_else(|| {
+                CustomError("Premium summons are not available right now.".to_owned())
             })?;
-            row.pulls.start_banner_bulk(&data.products, &inventory)?;
+            let inventory = data.stock(ctx, row.id, &reserved).await?;
+            row.pulls.start_banner_premium(
+                row.id,
+                products,
+                premium,
+                &inventory,
+                &pulled,
+                &profile.data.wishlist,
+                &mut *data.rng.lock().unwrap(),
+            )?;
+        }
+        Action::Reroll => {
+            let inventory = data.stock(ctx, row.id, &reserved).await?;
+            row.pulls.reroll(
+                &mut profile.data.rerolls,
+                row.id,
+                products,
+                &inventory,
+                &pulled,
+                &profile.data.wishlist,
+                &mut *data.rng.lock().unwrap(),
+            )?;
+            extra = Some("You rerolled your banner.".to_owned());
         }
         Action::Pull(index) => {
-            // NOTE: because we're not checking available inventory at this point, there is potential
-            // that two people pull at the same time, queuing up the last of one product, then both
-            // people pull that same product and it becomes oversold. Chances are very low though.
-            let product = row
-                .pulls
-                .check_slot(index)
-                .ok_or_else(|| CustomError("There is no currently active summon".to_owned()))?;
-            if let Err(error) = data
-                .inventory
-                .log_pull(
-                    interaction.user.id.to_string(),
-                    interaction_id.order_number,
-                    product.sku.clone(),
-                )
-                .await
-            {
-                log::error!("Error saving order to inventory: {}", error);
-            }
-
-            extra = Some(format!("You got **{}**!", product.name));
-            if let Err(error) = row.pulls.pull_slot(index) {
+            (extra, completed, summoned) = pull_slots(
+                ctx,
+                interaction,
+                data,
+                &mut row,
+                &database,
+                &reserved,
+                &mut profile,
+                &[index],
+            )
+            .await?;
+        }
+        Action::PullAll => {
+            let slots = row.pulls.remaining_slots(None);
+            (extra, completed, summoned) = pull_slots(
+                ctx,
+                interaction,
+                data,
+                &mut row,
+                &database,
+                &reserved,
+                &mut profile,
+                &slots,
+            )
+            .await?;
+        }
+        Action::PullPool(pool) => {
+            let slots = row.pulls.remaining_slots(Some(pool));
+            (extra, completed, summoned) = pull_slots(
+                ctx,
+                interaction,
+                data,
+                &mut row,
+                &database,
+                &reserved,
+                &mut profile,
+                &slots,
+            )
+            .await?;
+        }
+        Action::Shards => {
+            let cost = data
+                .products
+                .shards
+                .as_ref()
+                .map(|shards| shards.cost)
+                .filter(|_| row.id.tracks_inventory())
+                .ok_or_else(|| {
+                    CustomError("Shards cannot be spent on these summons.".to_owned())
+                })?;
+            // Summons bought with shards are the user's own, rather than part of the order.
+            let id = RecordId::User(interaction.user.id);
+            if row.id == id {
+                row.pulls.buy_single(&mut profile.data.shards, cost)?;
                 extra = Some(format!(
-                    "An error has occurred, please try again. ({error})"
+                    "You spent {cost} shards on an extra single summon."
                 ));
+            } else {
+                let mut bonus = database.remove(&id).unwrap_or_else(|| {
+                    Row::new(
+                        id,
+                        interaction.user.id.to_string(),
+                        interaction.user.name.to_owned(),
+                        PullsData::new(0, 0),
+                    )
+                });
+                bonus.pulls.buy_single(&mut profile.data.shards, cost)?;
+                extra = Some(format!(
+                    "You spent {cost} shards on an extra single summon, which has been added to your bonus summons. Use `/summon` without an order number to use it."
+                ));
+                bought = Some(bonus);
             }
         }
+        Action::History(page) => {
+            commands::check_owner(&row, &interaction.user)?;
+            let response = row
+                .pulls
+                .to_history_message(row.id, page)?
+                .into_edit_response();
+            interaction.edit_response(&ctx.http, response).await?;
+            return Ok(());
+        }
+        Action::Resolve { .. } | Action::Trade { .. } => {
+            unreachable!("Disputes and trades are handled before loading records")
+        }
         Action::Share => {
             let response = row
                 .pulls
                 .into_share_message(format!("<@{}>", row.discord_user_id))?;
-            ctx.http
-                .create_interaction_response(
-                    interaction.id,
-                    &interaction.token,
-                    &CreateInteractionResponse::Acknowledge,
-                    vec![],
-                )
-                .await?;
             log::info!(
                 "Sending share to {} ({})",
                 interaction.channel_id,
@@ -201,20 +364,204 @@ async fn handle_interaction(
         }
     }
 
-    let message = row.pulls.to_message(row.order_number, extra)?;
-    let (response, files) = message.into_interaction_response();
-    ctx.http
-        .create_interaction_response(
-            interaction.id,
-            &interaction.token,
-            &CreateInteractionResponse::UpdateMessage(response),
-            files,
-        )
+    let guild_id = row.pulls.set_guild(interaction.guild_id);
+    let message = row.pulls.to_message(row.id, data.wallet(&profile), extra)?;
+    interaction
+        .edit_response(&ctx.http, message.into_edit_response())
         .await?;
+    // Shards or rerolls spent are saved before what they bought, and any gained after what
+    // earned them, so that a failed save never leaves the user with something for nothing.
+    let mut profile = (profile.data.shards != wallet.shards
+        || profile.data.rerolls != wallet.rerolls)
+        .then_some(profile);
+    if let Some(spent) = profile.take_if(|profile| {
+        profile.data.shards < wallet.shards || profile.data.rerolls < wallet.rerolls
+    }) {
+        data.sheets.save_profile(spent).await?;
+    }
+    // Pulls are only counted once saved, so that a failed save is not counted.
+    let (id, owner) = (row.id, row.discord_user_id.clone());
     data.sheets.save(row).await?;
+    if id.tracks_inventory()
+        && let Some(guild_id) = guild_id
+        && let Some(stats) = data.stats.lock().unwrap().get_mut(&guild_id)
+    {
+        for product in &summoned {
+            stats.record(&owner, product);
+        }
+    }
+    if let Some(bought) = bought {
+        data.sheets.save(bought).await?;
+    }
+    if let Some(profile) = profile {
+        data.sheets.save_profile(profile).await?;
+    }
+    for set in completed {
+        award_set(
+            ctx,
+            interaction.guild_id,
+            Some(interaction.channel_id),
+            interaction.user.id,
+            set,
+        )
+        .await;
+    }
     Ok(())
 }
 
+/// Reveals the given slots of the active banner, describing the products summoned, along with
+/// any sets they complete. Returns the products summoned.
+#[allow(clippy::too_many_arguments)]
+async fn pull_slots<'a>(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    data: &'a Data,
+    row: &mut database::Row,
+    database: &HashMap<RecordId, database::Row>,
+    reserved: &HashMap<String, usize>,
+    profile: &mut Profile,
+    slots: &[usize],
+) -> Result<(Option<String>, Vec<&'a Set>, Vec<Product>), Error> {
+    let products = slots
+        .iter()
+        .map(|index| row.pulls.check_slot(*index).cloned())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| CustomError("There is no currently active summon".to_owned()))?;
+    if products.is_empty() {
+        return Err(CustomError("There is nothing left to summon.".to_owned()).into());
+    }
+    if row.pulls.is_abandoned() {
+        // An abandoned summon no longer holds its products, so they may have been
+        // promised to someone else since.
+        let inventory = data.stock(ctx, row.id, reserved).await?;
+        if products
+            .iter()
+            .any(|product| inventory.get(&product.sku).copied().unwrap_or(0) == 0)
+        {
+            return Err(CustomError(
+                "This summon was left for too long, and its products are no longer available. Please start a new summon."
+                    .to_owned(),
+            )
+            .into());
+        }
+    }
+
+    let mut owned: HashSet<_> = row
+        .pulls
+        .owned_skus()
+        .chain(
+            database
+                .values()
+                .filter(|other| other.discord_user_id == row.discord_user_id)
+                .flat_map(|other| other.pulls.owned_skus()),
+        )
+        .collect();
+    for index in slots {
+        row.pulls.pull_slot(*index)?;
+    }
+
+    if row.id.tracks_inventory()
+        && let Err(error) = data
+            .inventory
+            .log_pull(
+                interaction.user.id.to_string(),
+                row.id,
+                products.iter().map(|product| product.sku.clone()),
+            )
+            .await
+    {
+        log::error!("Error saving order to inventory: {}", error);
+    }
+
+    let mut lines = vec![];
+    let mut completed = vec![];
+    for product in &products {
+        let is_duplicate = !owned.insert(product.sku.clone());
+        if let Some(shards) = data.products.shards.as_ref()
+            && is_duplicate
+            && row.id.tracks_inventory()
+        {
+            let value = shards.value(product);
+            profile.data.shards += value;
+            lines.push(format!(
+                "You got **{}** again! It's a duplicate, so you also got **{} shards**.",
+                product.name, value
+            ));
+        } else {
+            lines.push(format!("You got **{}**!", product.name));
+        }
+        if profile.data.wishlist.contains(&product.sku) {
+            lines.push("It was on your wishlist!".to_owned());
+        }
+        if !is_duplicate {
+            for set in data
+                .products
+                .set
+                .iter()
+                .filter(|set| set.products.contains(&product.sku) && set.is_complete(&owned))
+            {
+                lines.push(format!("You completed the **{}** set!", set.name));
+                completed.push(set);
+            }
+        }
+    }
+    Ok((Some(lines.join("\n")), completed, products))
+}
+
+/// Gives a user the role for a set they have completed, and announces it in the channel if
+/// given. Failures are logged, as whatever completed the set has already been saved.
+async fn award_set(
+    ctx: &serenity::Context,
+    guild_id: Option<GuildId>,
+    announce: Option<ChannelId>,
+    user: UserId,
+    set: &Set,
+) {
+    log::info!("{} completed set {}", user, set.name);
+    if let Some(guild_id) = guild_id
+        && let Err(error) = ctx
+            .http
+            .add_member_role(
+                guild_id,
+                user,
+                set.role,
+                Some(&format!("Completed the {} set", set.name)),
+            )
+            .await
+    {
+        log::error!("Failed to give role for set {}: {}", set.name, error);
+    }
+    if let Some(channel_id) = announce
+        && let Err(error) = channel_id
+            .say(
+                &ctx.http,
+                format!("<@{}> has completed the **{}** set!", user, set.name),
+            )
+            .await
+    {
+        log::error!("Failed to announce set {}: {}", set.name, error);
+    }
+}
+
+/// Takes away the role for a set which a user no longer has every product of. Failures are
+/// logged, as whatever broke up the set has already been saved.
+async fn revoke_set(ctx: &serenity::Context, guild_id: Option<GuildId>, user: UserId, set: &Set) {
+    log::info!("{} no longer has set {}", user, set.name);
+    if let Some(guild_id) = guild_id
+        && let Err(error) = ctx
+            .http
+            .remove_member_role(
+                guild_id,
+                user,
+                set.role,
+                Some(&format!("No longer has the {} set", set.name)),
+            )
+            .await
+    {
+        log::error!("Failed to take role for set {}: {}", set.name, error);
+    }
+}
+
 fn event_handler<'a>(
     ctx: &'a serenity::Context,
     event: &'a FullEvent,
@@ -226,17 +573,17 @@ fn event_handler<'a>(
             FullEvent::InteractionCreate {
                 interaction: Interaction::Component(interaction),
             } => {
+                // Records may take longer to load than Discord waits for a response, especially
+                // while another interaction holds the lock, so the interaction is acknowledged
+                // first and its message edited once handled.
+                interaction.defer(&ctx.http).await?;
                 if let Err(error) = handle_interaction(ctx, interaction, data).await {
-                    ctx.http
-                        .create_interaction_response(
-                            interaction.id,
-                            &interaction.token,
-                            &CreateInteractionResponse::Message(
-                                CreateInteractionResponseMessage::new()
-                                    .ephemeral(true)
-                                    .content(format!("Error: {error}")),
-                            ),
-                            vec![],
+                    interaction
+                        .create_followup(
+                            &ctx.http,
+                            CreateInteractionResponseFollowup::new()
+                                .ephemeral(true)
+                                .content(format!("Error: {error}")),
                         )
                         .await?;
                 }
@@ -263,7 +610,11 @@ async fn main() {
     let products = std::fs::read_to_string("./products.toml").expect("products.toml must exist");
     let products = Products::from_toml(&products).unwrap();
     let assets_dir: PathBuf = "./assets/".parse().unwrap();
-    for product in products.iter() {
+    let daily_products = products
+        .daily
+        .iter()
+        .flat_map(|daily| daily.products.iter());
+    for product in products.iter().chain(daily_products) {
         if !assets_dir.join(&product.sku).with_extension("png").exists() {
             log::warn!("Missing image for {}", product.sku)
         }
@@ -271,7 +622,7 @@ async fn main() {
 
     println!("{}", products.distribution());
     println!(
-        "To add this bot to a server:\n\thttps://discord.com/api/oauth2/authorize?client_id={}&permissions=274877941760&scope=bot%20applications.commands",
+        "To add this bot to a server:\n\thttps://discord.com/api/oauth2/authorize?client_id={}&permissions=275146377216&scope=bot%20applications.commands",
         std::env::var("DISCORD_APPLICATION_ID").expect("DISCORD_APPLICATION_ID is required")
     );
 
@@ -279,6 +630,14 @@ async fn main() {
         log::warn!("Pulls are free!");
     }
 
+    let rng = match std::env::var("RNG_SEED") {
+        Ok(seed) => {
+            log::warn!("Randomness is seeded, summons are predictable!");
+            ChaCha20Rng::seed_from_u64(seed.parse().expect("RNG_SEED must be a number"))
+        }
+        Err(..) => ChaCha20Rng::from_entropy(),
+    };
+
     let mut sheets =
         Sheets::new(std::env::var("SHEETS_SHEET_ID").expect("SHEETS_SHEET_ID is required"));
     if std::env::var("ENV")
@@ -290,7 +649,24 @@ async fn main() {
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![summon()],
+            commands: vec![
+                commands::summon(),
+                commands::verify(),
+                commands::redeem(),
+                commands::promo(),
+                commands::daily(),
+                commands::wishlist(),
+                commands::history(),
+                commands::collection(),
+                commands::rates(),
+                commands::grant(),
+                commands::dispute(),
+                commands::transfer(),
+                commands::admin(),
+                commands::stats(),
+                commands::trade(),
+                commands::gift(),
+            ],
             event_handler,
             ..Default::default()
         })
@@ -308,11 +684,25 @@ async fn main() {
                     std::env::var("INVENTORY_ENABLED").is_ok(),
                 );
 
+                let staff = Staff::new(
+                    std::env::var("STAFF_CHANNEL_ID")
+                        .ok()
+                        .map(|id| id.parse().expect("STAFF_CHANNEL_ID must be a channel ID")),
+                    std::env::var("STAFF_ROLE_ID")
+                        .ok()
+                        .map(|id| id.parse().expect("STAFF_ROLE_ID must be a role ID")),
+                );
+
                 Ok(Data {
                     shopify,
                     sheets,
                     products,
                     inventory,
+                    staff,
+                    lock: Mutex::default(),
+                    rng: std::sync::Mutex::new(rng),
+                    stats: std::sync::Mutex::default(),
+                    trades: std::sync::Mutex::default(),
                 })
             })
         })
@@ -324,3 +714,37 @@ async fn main() {
         .await;
     client.unwrap().start().await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shopify::OrderNumber;
+
+    /// Discord rejects buttons whose custom ID is longer than 100 characters.
+    #[test]
+    fn custom_ids_fit_in_buttons() {
+        let user = UserId::new(u64::MAX);
+        let order: OrderNumber = u32::MAX.to_string().parse().unwrap();
+        for interaction in [
+            InteractionType {
+                id: RecordId::User(user),
+                action: Action::Trade {
+                    trade: u32::MAX,
+                    confirm: false,
+                },
+            },
+            InteractionType {
+                id: RecordId::Order(order),
+                action: Action::Resolve {
+                    claimant: user,
+                    approve: false,
+                },
+            },
+        ] {
+            let custom_id = serde_json::to_string(&interaction).unwrap();
+            assert!(custom_id.len() <= 100, "{custom_id} is too long");
+            let parsed: InteractionType = serde_json::from_str(&custom_id).unwrap();
+            assert_eq!(parsed.id, interaction.id);
+        }
+    }
+}
@@ -43,7 +43,7 @@ impl Client {
         let body = response.text().await?;
         log::trace!("http response body: {}", body);
         match serde_json::from_str(&body)? {
-            GraphQLResponse::Success { data, .. } => Ok(data),
+            GraphQLResponse::Success { data } => Ok(data),
             GraphQLResponse::Error { errors } => Err(Error::GraphQL(errors)),
         }
     }
@@ -64,6 +64,13 @@ impl Client {
                             nodes {{
                                 sku
                                 quantity
+                                variant {{
+                                    id
+                                }}
+                                product {{
+                                    handle
+                                    tags
+                                }}
                             }}
                         }}
                     }}
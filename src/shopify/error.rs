@@ -21,7 +21,15 @@ impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Custom(message) => message.fmt(f),
-            _ => write!(f, "An unexpected error has occurred: `{:?}`", self),
+            Self::GraphQL(errors) => {
+                write!(f, "An unexpected error has occurred:")?;
+                for error in errors {
+                    write!(f, " `{}`", error)?;
+                }
+                Ok(())
+            }
+            Self::Reqwest(error) => write!(f, "An unexpected error has occurred: `{}`", error),
+            Self::Json(error) => write!(f, "An unexpected error has occurred: `{}`", error),
         }
     }
 }
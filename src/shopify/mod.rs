@@ -19,4 +19,17 @@ pub struct Order {
 pub struct LineItem {
     pub sku: Option<String>,
     pub quantity: usize,
+    pub variant: Option<Variant>,
+    pub product: Option<Product>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Variant {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Product {
+    pub handle: String,
+    pub tags: Vec<String>,
 }
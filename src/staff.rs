@@ -0,0 +1,83 @@
+use crate::{Context, CustomError, Error};
+use poise::serenity_prelude::{ChannelId, CreateMessage, Http, Member, RoleId};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+pub struct Staff {
+    channel: Option<ChannelId>,
+    role: Option<RoleId>,
+    raised: Mutex<HashSet<&'static str>>,
+}
+
+/// Fails unless the user running the command has the staff role.
+pub async fn check(ctx: Context<'_>) -> Result<(), Error> {
+    let is_staff = match (ctx.data().staff.role, ctx.guild_id()) {
+        (Some(role), Some(guild_id)) => ctx.author().has_role(ctx, guild_id, role).await?,
+        _ => false,
+    };
+    if !is_staff {
+        log::warn!(
+            "{} attempted to use staff command {}",
+            ctx.author().name,
+            ctx.command().name
+        );
+        return Err(CustomError("This command is only available to staff.".to_owned()).into());
+    }
+    Ok(())
+}
+
+impl Staff {
+    pub fn new(channel: Option<ChannelId>, role: Option<RoleId>) -> Self {
+        if channel.is_none() {
+            log::warn!("STAFF_CHANNEL_ID is not set, staff alerts will only be logged");
+        }
+        if role.is_none() {
+            log::warn!("STAFF_ROLE_ID is not set, staff commands are disabled");
+        }
+        Self {
+            channel,
+            role,
+            raised: Mutex::default(),
+        }
+    }
+
+    /// Posts a message to the staff channel. Failing to do so is logged, but is not an error
+    /// for the user whose action triggered the alert.
+    pub async fn alert(&self, http: &Http, message: impl Into<String>) {
+        let message = message.into();
+        log::warn!("Staff alert: {}", message);
+        let Some(channel) = self.channel else {
+            return;
+        };
+        if let Err(error) = channel.say(http, message).await {
+            log::error!("Failed to send staff alert: {}", error);
+        }
+    }
+
+    /// Alerts staff of an ongoing condition, but only the first time it is raised until it
+    /// is cleared again.
+    pub async fn alert_once(&self, http: &Http, key: &'static str, message: impl Into<String>) {
+        if self.raised.lock().unwrap().insert(key) {
+            self.alert(http, message).await;
+        }
+    }
+
+    pub fn clear(&self, key: &'static str) {
+        self.raised.lock().unwrap().remove(key);
+    }
+
+    /// Whether a guild member has the staff role.
+    pub fn is_staff(&self, member: Option<&Member>) -> bool {
+        self.role
+            .is_some_and(|role| member.is_some_and(|member| member.roles.contains(&role)))
+    }
+
+    /// Posts a message to the staff channel for them to act on, failing if there is none.
+    pub async fn send(&self, http: &Http, message: CreateMessage) -> Result<(), Error> {
+        let channel = self
+            .channel
+            .ok_or_else(|| CustomError("Staff cannot be contacted right now.".to_owned()))?;
+        channel.send_message(http, message).await?;
+        Ok(())
+    }
+}
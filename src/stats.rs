@@ -0,0 +1,82 @@
+use crate::config::{Pool, Product, Products};
+use crate::database::Row;
+use poise::serenity_prelude::GuildId;
+use std::collections::HashMap;
+
+/// Totals across every summon in a server, for the leaderboard. Daily summons are left out, as
+/// they are drawn from their own catalogue, whose rarities would skew what counts as rare.
+#[derive(Debug)]
+pub struct Stats {
+    /// Products with a rarity below this count as rare pulls.
+    threshold: f64,
+    pub summons: usize,
+    /// Names and counts of each product pulled, by SKU.
+    pub products: HashMap<String, (String, usize)>,
+    pub pools: HashMap<Pool, usize>,
+    /// Total and rare pulls, by Discord user ID.
+    pub users: HashMap<String, (usize, usize)>,
+}
+
+impl Stats {
+    pub fn new<'a>(
+        guild_id: GuildId,
+        products: &Products,
+        rows: impl IntoIterator<Item = &'a Row>,
+    ) -> Self {
+        let count = products.iter().count();
+        let mut stats = Self {
+            threshold: products.iter().map(|product| product.rarity).sum::<f64>()
+                / count.max(1) as f64,
+            summons: 0,
+            products: HashMap::new(),
+            pools: HashMap::new(),
+            users: HashMap::new(),
+        };
+        for row in rows.into_iter().filter(|row| row.id.tracks_inventory()) {
+            for product in row.pulls.pulled_products_in(guild_id) {
+                stats.record(&row.discord_user_id, product);
+            }
+        }
+        stats
+    }
+
+    pub fn record(&mut self, discord_user_id: &str, product: &Product) {
+        self.summons += 1;
+        self.products
+            .entry(product.sku.clone())
+            .or_insert_with(|| (product.name.clone(), 0))
+            .1 += 1;
+        *self.pools.entry(product.pool).or_default() += 1;
+        let user = self.users.entry(discord_user_id.to_owned()).or_default();
+        user.0 += 1;
+        if product.rarity < self.threshold {
+            user.1 += 1;
+        }
+    }
+
+    /// The products pulled most often, most first.
+    pub fn most_pulled(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut products: Vec<_> = self
+            .products
+            .values()
+            .map(|(name, pulls)| (name.as_str(), *pulls))
+            .collect();
+        products.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        products.truncate(count);
+        products
+    }
+
+    /// The users with the highest proportion of rare pulls, among those with at least
+    /// `min_pulls` pulls.
+    pub fn luckiest(&self, count: usize, min_pulls: usize) -> Vec<(&str, f64)> {
+        let mut users: Vec<_> = self
+            .users
+            .iter()
+            .filter(|(_, (pulls, _))| *pulls >= min_pulls)
+            .map(|(user, (pulls, rare))| (user.as_str(), *rare as f64 / *pulls as f64))
+            .collect();
+        users.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+        users.truncate(count);
+        users
+    }
+}
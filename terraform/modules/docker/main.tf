@@ -46,7 +46,7 @@ resource "docker_container" "discord-gacha" {
     read_only      = true
   }
 
-  env = [
+  env = concat([
     "DISCORD_APPLICATION_ID=${var.discord_application_id}",
     "DISCORD_TOKEN=${var.discord_token}",
     "SHEETS_SHEET_ID=${var.sheets_sheet_id}",
@@ -60,7 +60,11 @@ resource "docker_container" "discord-gacha" {
     "INVENTORY_URL=${var.inventory_url}",
     "INVENTORY_ENABLED=${var.inventory_enabled}",
     "RUST_LOG=error,serenity=warn,discord_gacha=${var.log_level}",
-  ]
+    ], var.staff_channel_id == null ? [] : [
+    "STAFF_CHANNEL_ID=${var.staff_channel_id}",
+    ], var.staff_role_id == null ? [] : [
+    "STAFF_ROLE_ID=${var.staff_role_id}",
+  ])
 
   lifecycle {
     replace_triggered_by = [
@@ -87,6 +87,16 @@ variable "inventory_enabled" {
   default = true
 }
 
+variable "staff_channel_id" {
+  type    = string
+  default = null
+}
+
+variable "staff_role_id" {
+  type    = string
+  default = null
+}
+
 variable "log_level" {
   type    = string
   default = "debug"